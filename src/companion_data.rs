@@ -0,0 +1,112 @@
+use std::fs;
+use std::path::Path;
+
+use crate::engine_data::EngineSource;
+use crate::iwad_data::IWadID;
+
+//------------------------------------------------------------------------------
+// ENUM: CompanionKind
+//------------------------------------------------------------------------------
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum CompanionKind {
+    // DEHACKED patch needed to play the game
+    Dehacked,
+    // Spoken dialogue (Strife)
+    Voices,
+}
+
+//------------------------------------------------------------------------------
+// ENUM: CompanionLoad
+//------------------------------------------------------------------------------
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum CompanionLoad {
+    // Engine supports the game natively
+    NotNeeded,
+    // Engine loads the file from the IWAD folder itself
+    Automatic,
+    // File is passed with the given switch
+    Switch(&'static str),
+}
+
+impl CompanionKind {
+    //-----------------------------------
+    // Load function
+    //-----------------------------------
+    // Chocolate Strife and GZDoom both load voices.wad from the IWAD folder.
+    // Deathkings of the Dark Citadel is not a companion: hexdd.wad is listed as
+    // a database expansion, and every engine running Hexen (Chocolate and Crispy
+    // Hexen, DSDA-Doom, GZDoom and VKDoom) loads it with a plain -file switch
+    pub fn load(self, source: EngineSource) -> CompanionLoad {
+        match (self, source) {
+            (Self::Dehacked, EngineSource::ZDoom) => CompanionLoad::NotNeeded,
+            (Self::Dehacked, EngineSource::Chocolate) => CompanionLoad::Automatic,
+            (Self::Dehacked, _) => CompanionLoad::Switch("-deh"),
+            (Self::Voices, EngineSource::Chocolate | EngineSource::ZDoom) => CompanionLoad::Automatic,
+            (Self::Voices, _) => CompanionLoad::Switch("-file"),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// STRUCT: CompanionData
+//------------------------------------------------------------------------------
+#[derive(Debug)]
+pub struct CompanionData {
+    pub id: IWadID,
+    pub name: &'static str,
+    pub filename: &'static str,
+    pub kind: CompanionKind,
+    pub required: bool,
+}
+
+//------------------------------------------------------------------------------
+// COMPANION DATA
+//------------------------------------------------------------------------------
+// Auxiliary files searched for next to the IWAD
+pub static COMPANION_ARRAY: [CompanionData; 2] = [
+    CompanionData {
+        id: IWadID::CHEX,
+        name: "Chex Quest DEHACKED patch",
+        filename: "chex.deh",
+        kind: CompanionKind::Dehacked,
+        required: true,
+    },
+    CompanionData {
+        id: IWadID::STRIFE,
+        name: "Strife voices",
+        filename: "voices.wad",
+        kind: CompanionKind::Voices,
+        required: false,
+    },
+];
+
+//------------------------------------------------------------------------------
+// GLOBAL: Functions
+//------------------------------------------------------------------------------
+//---------------------------------------
+// Find companions function
+//---------------------------------------
+// Returns the companion files of a game found in the IWAD folder (file names
+// are compared case-insensitively)
+pub fn find_companions(id: IWadID, iwad_file: &Path) -> Vec<(&'static CompanionData, String)> {
+    let Some(entries) = iwad_file.parent().and_then(|folder| fs::read_dir(folder).ok()) else {
+        return vec![]
+    };
+
+    let files: Vec<_> = entries.flatten()
+        .map(|entry| entry.path())
+        .collect();
+
+    COMPANION_ARRAY.iter()
+        .filter(|data| data.id == id)
+        .filter_map(|data| {
+            files.iter()
+                .find(|file| {
+                    file.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.eq_ignore_ascii_case(data.filename))
+                })
+                .map(|file| (data, file.display().to_string()))
+        })
+        .collect()
+}
@@ -1,4 +1,4 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::sync::OnceLock;
 use std::path::Path;
 
@@ -8,7 +8,7 @@ use adw::prelude::*;
 use glib::clone;
 use glib::subclass::Signal;
 
-use crate::engine_data::ENGINE_ARRAY;
+use crate::engine_data::{EngineFeatures, ENGINE_ARRAY};
 use crate::engine_object::EngineObject;
 use crate::iwad_data::IWadID;
 
@@ -21,7 +21,8 @@ mod imp {
     //-----------------------------------
     // Private structure
     //-----------------------------------
-    #[derive(Default, gtk::CompositeTemplate)]
+    #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+    #[properties(wrapper_type = super::EngineComboRow)]
     #[template(resource = "/com/github/DHXS-Launcher/ui/engine_combo_row.ui")]
     pub struct EngineComboRow {
         #[template_child]
@@ -34,7 +35,10 @@ mod imp {
         #[template_child]
         pub(super) filter: TemplateChild<gtk::CustomFilter>,
 
-        pub(super) iwad_id: RefCell<Option<IWadID>>,
+        pub(super) iwad_filter: RefCell<Option<(IWadID, EngineFeatures)>>,
+
+        #[property(get, set)]
+        hide_incompatible: Cell<bool>,
     }
 
     //-----------------------------------
@@ -57,6 +61,7 @@ mod imp {
         }
     }
 
+    #[glib::derived_properties]
     impl ObjectImpl for EngineComboRow {
         //---------------------------------------
         // Custom signals
@@ -107,6 +112,11 @@ impl EngineComboRow {
     fn setup_signals(&self) {
         let imp = self.imp();
 
+        // Hide incompatible property notify signal
+        self.connect_hide_incompatible_notify(|row| {
+            row.imp().filter.changed(gtk::FilterChange::Different);
+        });
+
         // Settings button clicked signal
         imp.settings_button.connect_clicked(clone!(
             #[weak(rename_to = row)] self,
@@ -135,12 +145,13 @@ impl EngineComboRow {
             #[weak] imp,
             #[upgrade_or] false,
             move |item| {
-                if let Some(id) = &*imp.iwad_id.borrow() {
-                    item
+                if let Some((id, requires)) = &*imp.iwad_filter.borrow() {
+                    let engine = item
                         .downcast_ref::<EngineObject>()
-                        .expect("Must be a 'EngineObject'")
-                        .games()
-                        .intersects(*id)
+                        .expect("Must be a 'EngineObject'");
+
+                    engine.games().intersects(*id) && engine.features().contains(*requires) &&
+                        !(imp.hide_incompatible.get() && engine.incompatible())
                 } else {
                     false
                 }
@@ -151,10 +162,29 @@ impl EngineComboRow {
     //-----------------------------------
     // Public filter engines function
     //-----------------------------------
-    pub fn filter_engines(&self, iwad_id: Option<IWadID>) {
+    pub fn filter_engines(&self, iwad_filter: Option<(IWadID, EngineFeatures)>) {
+        let imp = self.imp();
+
+        imp.iwad_filter.replace(iwad_filter);
+
+        imp.filter.changed(gtk::FilterChange::Different);
+
+        imp.settings_button.set_sensitive(imp.sort_model.n_items() > 0);
+    }
+
+    //-----------------------------------
+    // Public set PWAD requirements function
+    //-----------------------------------
+    // Marks engines lacking features required by the selected PWADs
+    pub fn set_pwad_requirements(&self, requires: EngineFeatures) {
         let imp = self.imp();
 
-        imp.iwad_id.replace(iwad_id);
+        for engine in imp.model.iter::<EngineObject>().flatten() {
+            let missing = requires - engine.features();
+
+            engine.set_incompatible(!missing.is_empty());
+            engine.set_incompatible_reason(if missing.is_empty() { String::new() } else { format!("Does not support {}", missing.display_name()) });
+        }
 
         imp.filter.changed(gtk::FilterChange::Different);
 
@@ -1,6 +1,10 @@
+use std::path::Path;
+
 use gtk::glib;
+use gtk::prelude::ToValue;
 
 use crate::iwad_data::IWadID;
+use crate::wad_reader::{ArchiveFormat, WadFile};
 
 //------------------------------------------------------------------------------
 // ENUM: EngineSource
@@ -18,6 +22,168 @@ pub enum EngineSource {
     ZDoom,
 }
 
+impl EngineSource {
+    //-----------------------------------
+    // Groups files function
+    //-----------------------------------
+    // Whether files must follow a single occurrence of their switch (only the
+    // first -file switch is honored by vanilla-derived engines)
+    pub fn groups_files(self) -> bool {
+        self != Self::ZDoom
+    }
+}
+
+//------------------------------------------------------------------------------
+// ENUM: FileKind
+//------------------------------------------------------------------------------
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum FileKind {
+    Wad,
+    // WAD replacing sprites or flats (merged into the IWAD by vanilla engines)
+    SpriteWad,
+    Dehacked,
+    Zip,
+    Pk3,
+    Pk7,
+    Kpf,
+}
+
+impl FileKind {
+    //-----------------------------------
+    // From path function
+    //-----------------------------------
+    // Detected from the file extension, then from the file signature
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "deh" | "bex" => return Self::Dehacked,
+            "pk3" | "ipk3" => return Self::Pk3,
+            "pk7" => return Self::Pk7,
+            "kpf" => return Self::Kpf,
+            _ => {}
+        }
+
+        match ArchiveFormat::detect(path) {
+            Ok(Some(ArchiveFormat::Zip)) => Self::Zip,
+            Ok(Some(ArchiveFormat::SevenZip)) => Self::Pk7,
+            _ => {
+                let has_sprites = WadFile::open(path)
+                    .is_ok_and(|wad| ["S_START", "SS_START", "F_START", "FF_START"].iter().any(|name| wad.has_lump(name)));
+
+                if has_sprites { Self::SpriteWad } else { Self::Wad }
+            }
+        }
+    }
+
+    //-----------------------------------
+    // Display name function
+    //-----------------------------------
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::Wad | Self::SpriteWad => "WAD files",
+            Self::Dehacked => "DeHackEd patches",
+            Self::Zip => "ZIP archives",
+            Self::Pk3 => "PK3 archives",
+            Self::Pk7 => "PK7 archives",
+            Self::Kpf => "KPF archives",
+        }
+    }
+
+    //-----------------------------------
+    // Load function
+    //-----------------------------------
+    // Switch used to pass the file to the engine (None if unsupported). Chocolate
+    // Doom loads -merge files before -file files, so sprite WADs move ahead of
+    // other WADs regardless of the user order (noted in the PWAD files dialog)
+    pub fn load(self, source: EngineSource) -> Option<&'static str> {
+        match (self, source) {
+            (Self::Dehacked, _) => Some("-deh"),
+            (_, EngineSource::ZDoom) => Some("-file"),
+            (Self::SpriteWad, EngineSource::Chocolate) => Some("-merge"),
+            (Self::Wad | Self::SpriteWad, _) => Some("-file"),
+            _ => None
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// FLAGS: EngineFeatures
+//------------------------------------------------------------------------------
+#[glib::flags(name = "EngineFeatures")]
+pub enum EngineFeatures {
+    #[flags_value(name = "KPF archives")]
+    KPF            = 0b0000_0000_0001,
+    #[flags_value(name = "Re-release extras")]
+    EXTRAS         = 0b0000_0000_0010,
+    #[flags_value(name = "id24")]
+    ID24           = 0b0000_0000_0100,
+    #[flags_value(name = "DeHackEd")]
+    DEHACKED       = 0b0000_0000_1000,
+    #[flags_value(name = "DEHEXTRA")]
+    DEHEXTRA       = 0b0000_0001_0000,
+    #[flags_value(name = "MBF21")]
+    MBF21          = 0b0000_0010_0000,
+    #[flags_value(name = "UMAPINFO")]
+    UMAPINFO       = 0b0000_0100_0000,
+    #[flags_value(name = "UDMF maps")]
+    UDMF           = 0b0000_1000_0000,
+    #[flags_value(name = "Hexen format maps")]
+    HEXEN_MAPS     = 0b0001_0000_0000,
+    #[flags_value(name = "ZScript/DECORATE")]
+    ZDOOM          = 0b0010_0000_0000,
+    #[flags_value(name = "Embedded DeHackEd lumps")]
+    DEHACKED_LUMPS = 0b0100_0000_0000,
+
+    #[flags_value(skip)]
+    BOOM_MODS = Self::DEHACKED.bits() | Self::DEHACKED_LUMPS.bits() | Self::DEHEXTRA.bits() | Self::MBF21.bits() | Self::UMAPINFO.bits(),
+}
+
+impl Default for EngineFeatures {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl EngineFeatures {
+    //-----------------------------------
+    // Display name function
+    //-----------------------------------
+    pub fn display_name(self) -> String {
+        glib::FlagsValue::from_value(&self.to_value())
+            .map(|(_, values)| {
+                values.iter()
+                    .map(|value| value.name())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            })
+            .unwrap_or_default()
+    }
+
+    //-----------------------------------
+    // From key function
+    //-----------------------------------
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "KPF" => Some(Self::KPF),
+            "EXTRAS" => Some(Self::EXTRAS),
+            "ID24" => Some(Self::ID24),
+            "DEHACKED" => Some(Self::DEHACKED),
+            "DEHEXTRA" => Some(Self::DEHEXTRA),
+            "MBF21" => Some(Self::MBF21),
+            "UMAPINFO" => Some(Self::UMAPINFO),
+            "UDMF" => Some(Self::UDMF),
+            "HEXEN_MAPS" => Some(Self::HEXEN_MAPS),
+            "ZDOOM" => Some(Self::ZDOOM),
+            "DEHACKED_LUMPS" => Some(Self::DEHACKED_LUMPS),
+            _ => None
+        }
+    }
+}
+
 //------------------------------------------------------------------------------
 // STRUCT: EngineData
 //------------------------------------------------------------------------------
@@ -27,6 +193,7 @@ pub struct EngineData<'a> {
     pub description: &'a str,
     pub source: EngineSource,
     pub games: IWadID,
+    pub features: EngineFeatures,
     pub doom_path: &'a str,
     pub heretic_path: Option<&'a str>,
     pub hexen_path: Option<&'a str>,
@@ -42,7 +209,8 @@ pub const ENGINE_ARRAY: [EngineData; 7] = [
         name: "Chocolate Doom",
         description: "Historically-accurate Doom, Heretic, Hexen, and Strife port",
         source: EngineSource::Chocolate,
-        games: IWadID::ALL,
+        games: IWadID::ALL_ORIGINAL.union(IWadID::CHEX),
+        features: EngineFeatures::DEHACKED,
         doom_path: "/usr/bin/chocolate-doom",
         heretic_path: Some("/usr/bin/chocolate-heretic"),
         hexen_path: Some("/usr/bin/chocolate-hexen"),
@@ -53,7 +221,8 @@ pub const ENGINE_ARRAY: [EngineData; 7] = [
         name: "Crispy Doom",
         description: "Vanilla-compatible enhanced Doom engine",
         source: EngineSource::Chocolate,
-        games: IWadID::ALL,
+        games: IWadID::ALL_ORIGINAL.union(IWadID::CHEX),
+        features: EngineFeatures::DEHACKED.union(EngineFeatures::DEHACKED_LUMPS).union(EngineFeatures::DEHEXTRA),
         doom_path: "/usr/bin/crispy-doom",
         heretic_path: Some("/usr/bin/crispy-heretic"),
         hexen_path: Some("/usr/bin/crispy-hexen"),
@@ -64,7 +233,8 @@ pub const ENGINE_ARRAY: [EngineData; 7] = [
         name: "DSDA-Doom",
         description: "Fork of PrBoom+ with extra tooling for demo recording and playback, with a focus on speedrunning",
         source: EngineSource::PrBoom,
-        games: IWadID::ALL_NO_STRIFE_HACX,
+        games: IWadID::ALL_NO_STRIFE_HACX.union(IWadID::CHEX).union(IWadID::REKKR),
+        features: EngineFeatures::ID24.union(EngineFeatures::BOOM_MODS).union(EngineFeatures::UDMF).union(EngineFeatures::HEXEN_MAPS),
         doom_path: "/usr/bin/dsda-doom",
         heretic_path: None,
         hexen_path: None,
@@ -76,6 +246,7 @@ pub const ENGINE_ARRAY: [EngineData; 7] = [
         description: "Feature centric port for all Doom engine games",
         source: EngineSource::ZDoom,
         games: IWadID::ALL,
+        features: EngineFeatures::KPF.union(EngineFeatures::EXTRAS).union(EngineFeatures::BOOM_MODS).union(EngineFeatures::UDMF).union(EngineFeatures::HEXEN_MAPS).union(EngineFeatures::ZDOOM),
         doom_path: "/usr/bin/gzdoom",
         heretic_path: None,
         hexen_path: None,
@@ -86,7 +257,8 @@ pub const ENGINE_ARRAY: [EngineData; 7] = [
         name: "Nugget Doom",
         description: "Fork of Woof! with additional features",
         source: EngineSource::WinMBF,
-        games: IWadID::ANY_DOOM_HACX,
+        games: IWadID::ANY_DOOM_HACX.union(IWadID::REKKR),
+        features: EngineFeatures::EXTRAS.union(EngineFeatures::ID24).union(EngineFeatures::BOOM_MODS),
         doom_path: "/usr/bin/nugget-doom",
         heretic_path: None,
         hexen_path: None,
@@ -98,6 +270,7 @@ pub const ENGINE_ARRAY: [EngineData; 7] = [
         description: "VKDoom is a source port based on the DOOM engine with a focus on Vulkan and modern computers",
         source: EngineSource::ZDoom,
         games: IWadID::ALL,
+        features: EngineFeatures::KPF.union(EngineFeatures::EXTRAS).union(EngineFeatures::BOOM_MODS).union(EngineFeatures::UDMF).union(EngineFeatures::HEXEN_MAPS).union(EngineFeatures::ZDOOM),
         doom_path: "/usr/bin/vkdoom",
         heretic_path: None,
         hexen_path: None,
@@ -108,7 +281,8 @@ pub const ENGINE_ARRAY: [EngineData; 7] = [
         name: "Woof!",
         description: "Woof! is a continuation of Lee Killough's Doom source port MBF targeted at modern systems",
         source: EngineSource::WinMBF,
-        games: IWadID::ANY_DOOM_HACX,
+        games: IWadID::ANY_DOOM_HACX.union(IWadID::REKKR),
+        features: EngineFeatures::EXTRAS.union(EngineFeatures::ID24).union(EngineFeatures::BOOM_MODS),
         doom_path: "/usr/bin/woof",
         heretic_path: None,
         hexen_path: None,
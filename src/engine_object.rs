@@ -5,7 +5,7 @@ use gtk::subclass::prelude::*;
 use gtk::prelude::ObjectExt;
 
 use crate::iwad_data::IWadID;
-use crate::engine_data::{EngineData, EngineSource};
+use crate::engine_data::{EngineData, EngineFeatures, EngineSource};
 use crate::engine_settings::EngineSettings;
 
 //------------------------------------------------------------------------------
@@ -27,6 +27,8 @@ mod imp {
         #[property(get, set)]
         games: Cell<IWadID>,
         #[property(get, set)]
+        features: Cell<EngineFeatures>,
+        #[property(get, set)]
         doom_path: RefCell<String>,
         #[property(get, set, nullable)]
         heretic_path: RefCell<Option<String>>,
@@ -39,6 +41,11 @@ mod imp {
         #[property(get, set, builder(EngineSource::default()))]
         source: Cell<EngineSource>,
 
+        #[property(get, set)]
+        incompatible: Cell<bool>,
+        #[property(get, set)]
+        incompatible_reason: RefCell<String>,
+
         #[property(get)]
         settings: RefCell<EngineSettings>,
     }
@@ -73,6 +80,7 @@ impl EngineObject {
             .property("name", data.name)
             .property("description", data.description)
             .property("games", data.games)
+            .property("features", data.features)
             .property("doom-path", data.doom_path)
             .property("heretic-path", data.heretic_path)
             .property("hexen-path", data.hexen_path)
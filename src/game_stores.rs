@@ -0,0 +1,290 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use glob::glob;
+
+use crate::iwad_data::IWAD_EXTENSIONS;
+use crate::iwad_paths::{IWadPath, IWadPathSource};
+use crate::utils::env_expand;
+
+//------------------------------------------------------------------------------
+// CONST VARIABLES
+//------------------------------------------------------------------------------
+// Steam app IDs of games that ship IWADs
+const STEAM_APP_IDS: [u32; 9] = [
+    2280,   // Ultimate Doom / DOOM + DOOM II
+    2290,   // Final Doom
+    2300,   // Doom II
+    2360,   // Hexen: Beyond Heretic
+    2370,   // Hexen: Deathkings of the Dark Citadel
+    2390,   // Heretic: Shadow of the Serpent Riders
+    9160,   // Master Levels for Doom II
+    208200, // DOOM 3: BFG Edition
+    317040, // The Original Strife: Veteran Edition
+];
+
+const STEAM_ROOTS: [&str; 4] = [
+    "$HOME/.steam/steam",
+    "$HOME/.local/share/Steam",
+    "$HOME/.var/app/com.valvesoftware.Steam/.local/share/Steam",
+    "$HOME/snap/steam/common/.local/share/Steam",
+];
+
+const GOG_FOLDERS: [&str; 2] = [
+    "$HOME/GOG Games",
+    "$HOME/Games/GOG",
+];
+
+const HEROIC_INSTALLED_FILES: [&str; 2] = [
+    "$HOME/.config/heroic/gog_store/installed.json",
+    "$HOME/.var/app/com.heroicgameslauncher.hgl/config/heroic/gog_store/installed.json",
+];
+
+const LUTRIS_GAME_FOLDERS: [&str; 2] = [
+    "$HOME/.config/lutris/games",
+    "$HOME/.local/share/lutris/games",
+];
+
+// Keywords used to skip non-Doom games in GOG, Heroic and Lutris installs
+const GAME_KEYWORDS: [&str; 6] = ["doom", "heretic", "hexen", "strife", "chex", "hacx"];
+
+// Maximum folder depth searched below a game installation
+const MAX_SEARCH_DEPTH: usize = 4;
+
+//------------------------------------------------------------------------------
+// STATIC VARIABLES
+//------------------------------------------------------------------------------
+// Store folders found during this session (walked once, until refreshed)
+static STORE_PATHS: Mutex<Option<Vec<IWadPath>>> = Mutex::new(None);
+
+//------------------------------------------------------------------------------
+// GLOBAL: Helper functions
+//------------------------------------------------------------------------------
+//---------------------------------------
+// Expand path helper function
+//---------------------------------------
+fn expand_path(path: &str) -> PathBuf {
+    PathBuf::from(env_expand(path))
+}
+
+//---------------------------------------
+// Quoted values helper function
+//---------------------------------------
+// Extracts the values of a key from VDF/ACF ("key" "value") or JSON
+// ("key": "value") text
+fn quoted_values(text: &str, key: &str) -> Vec<String> {
+    let pattern = format!("\"{key}\"");
+
+    text.match_indices(&pattern)
+        .filter_map(|(index, _)| {
+            let rest = text[index + pattern.len()..].trim_start().trim_start_matches(':').trim_start();
+
+            let value = rest.strip_prefix('"')?;
+
+            let mut result = String::new();
+            let mut chars = value.chars();
+
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => return Some(result),
+                    '\\' => result.push(chars.next()?),
+                    _ => result.push(c)
+                }
+            }
+
+            None
+        })
+        .collect()
+}
+
+//---------------------------------------
+// Is game install helper function
+//---------------------------------------
+fn is_game_install(path: &Path) -> bool {
+    let path = path.display().to_string().to_lowercase();
+
+    GAME_KEYWORDS.iter().any(|keyword| path.contains(keyword))
+}
+
+//---------------------------------------
+// WAD folders helper function
+//---------------------------------------
+// Recursively collects folders that contain IWAD-like files
+fn wad_folders(folder: &Path, depth: usize, folders: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return
+    };
+
+    let mut has_wads = false;
+    let mut subfolders: Vec<PathBuf> = vec![];
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            subfolders.push(path);
+        } else if path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| IWAD_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        {
+            has_wads = true;
+        }
+    }
+
+    if has_wads {
+        folders.push(folder.to_path_buf());
+    }
+
+    if depth > 0 {
+        subfolders.sort();
+
+        for subfolder in subfolders {
+            wad_folders(&subfolder, depth - 1, folders);
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// GLOBAL: Store functions
+//------------------------------------------------------------------------------
+//---------------------------------------
+// Steam installs function
+//---------------------------------------
+fn steam_installs() -> Vec<PathBuf> {
+    let mut installs: Vec<PathBuf> = vec![];
+
+    // Get library folders (the Steam root is a library itself)
+    let mut libraries: Vec<PathBuf> = vec![];
+
+    for root in STEAM_ROOTS.iter().map(|root| expand_path(root)) {
+        let Ok(root) = root.canonicalize() else {
+            continue
+        };
+
+        libraries.push(root.clone());
+
+        if let Ok(text) = fs::read_to_string(root.join("steamapps/libraryfolders.vdf")) {
+            libraries.extend(quoted_values(&text, "path").into_iter().map(PathBuf::from));
+        }
+    }
+
+    libraries.sort();
+    libraries.dedup();
+
+    for library in libraries {
+        let steamapps = library.join("steamapps");
+
+        // Native (and Proton) game installs from app manifests
+        for app_id in STEAM_APP_IDS {
+            let Ok(text) = fs::read_to_string(steamapps.join(format!("appmanifest_{app_id}.acf"))) else {
+                continue
+            };
+
+            installs.extend(quoted_values(&text, "installdir").into_iter()
+                .map(|dir| steamapps.join("common").join(dir))
+            );
+        }
+
+        // Games installed inside Proton prefixes
+        let pattern = steamapps.join("compatdata/*/pfx/drive_c/*/*");
+
+        installs.extend(glob(&pattern.display().to_string()).into_iter()
+            .flat_map(|paths| paths.flatten())
+            .filter(|path| path.is_dir() && is_game_install(path))
+        );
+    }
+
+    installs
+}
+
+//---------------------------------------
+// GOG installs function
+//---------------------------------------
+fn gog_installs() -> Vec<PathBuf> {
+    GOG_FOLDERS.iter()
+        .flat_map(|folder| fs::read_dir(expand_path(folder)).into_iter().flatten())
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && is_game_install(path))
+        .collect()
+}
+
+//---------------------------------------
+// Heroic installs function
+//---------------------------------------
+fn heroic_installs() -> Vec<PathBuf> {
+    HEROIC_INSTALLED_FILES.iter()
+        .filter_map(|file| fs::read_to_string(expand_path(file)).ok())
+        .flat_map(|text| quoted_values(&text, "install_path"))
+        .map(PathBuf::from)
+        .filter(|path| is_game_install(path))
+        .collect()
+}
+
+//---------------------------------------
+// Lutris installs function
+//---------------------------------------
+fn lutris_installs() -> Vec<PathBuf> {
+    LUTRIS_GAME_FOLDERS.iter()
+        .flat_map(|folder| fs::read_dir(expand_path(folder)).into_iter().flatten())
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_game_install(path))
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .flat_map(|text| {
+            // Game folder and Wine prefix from game config (YAML)
+            text.lines()
+                .filter_map(|line| {
+                    let (key, value) = line.trim().split_once(':')?;
+
+                    matches!(key, "prefix" | "working_dir" | "game_path")
+                        .then(|| PathBuf::from(value.trim().trim_matches(['"', '\''])))
+                })
+                .collect::<Vec<PathBuf>>()
+        })
+        .collect()
+}
+
+//---------------------------------------
+// Store IWAD paths function
+//---------------------------------------
+pub fn store_iwad_paths() -> Vec<IWadPath> {
+    let mut cache = STORE_PATHS.lock().unwrap_or_else(|error| error.into_inner());
+
+    cache.get_or_insert_with(scan_store_iwad_paths).clone()
+}
+
+//---------------------------------------
+// Refresh store IWAD paths function
+//---------------------------------------
+// Clears the cached store folders, next call to store_iwad_paths() rescans
+pub fn refresh_store_iwad_paths() {
+    STORE_PATHS.lock().unwrap_or_else(|error| error.into_inner()).take();
+}
+
+//---------------------------------------
+// Scan store IWAD paths function
+//---------------------------------------
+fn scan_store_iwad_paths() -> Vec<IWadPath> {
+    let stores = [
+        (steam_installs(), IWadPathSource::Steam),
+        (gog_installs(), IWadPathSource::Gog),
+        (heroic_installs(), IWadPathSource::Heroic),
+        (lutris_installs(), IWadPathSource::Lutris),
+    ];
+
+    let mut paths: Vec<IWadPath> = vec![];
+
+    for (installs, source) in stores {
+        for install in installs {
+            let mut folders: Vec<PathBuf> = vec![];
+
+            wad_folders(&install, MAX_SEARCH_DEPTH, &mut folders);
+
+            paths.extend(folders.into_iter()
+                .map(|folder| IWadPath { path: folder.display().to_string(), source })
+            );
+        }
+    }
+
+    paths
+}
@@ -0,0 +1,765 @@
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use gtk::{gio, glib};
+use adw::subclass::prelude::*;
+use adw::prelude::*;
+use glib::clone;
+use glib::subclass::Signal;
+
+use crate::companion_data::COMPANION_ARRAY;
+use crate::iwad_data::{IWadMatch, IWAD_EXTENSIONS};
+use crate::pwad_data::PWAD_RESOURCE_EXTENSIONS;
+use crate::utils::{checksum, crc32, file_to_path, unique_path};
+use crate::wad_database::WadDatabase;
+use crate::wad_reader::ZipArchive;
+
+//------------------------------------------------------------------------------
+// CONST VARIABLES
+//------------------------------------------------------------------------------
+// Maximum folder depth scanned (CD layouts nest files a few levels deep)
+const MAX_SCAN_DEPTH: usize = 8;
+
+// Archive entries larger than this are ignored (largest IWADs are < 100 MB)
+const MAX_ARCHIVE_ENTRY_SIZE: u32 = 512 * 1024 * 1024;
+
+// Staging folders for extracted archive entries (one per scan, in the user
+// cache folder)
+const STAGING_FOLDER: &str = "dhxs-launcher/import";
+
+// Suffix of files being imported (renamed when complete)
+const TEMP_SUFFIX: &str = "importing";
+
+//------------------------------------------------------------------------------
+// ENUM: ImportMode
+//------------------------------------------------------------------------------
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+enum ImportMode {
+    Copy,
+    Symlink,
+}
+
+//------------------------------------------------------------------------------
+// ENUM: ConflictMode
+//------------------------------------------------------------------------------
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+enum ConflictMode {
+    Skip,
+    Replace,
+    KeepBoth,
+}
+
+//------------------------------------------------------------------------------
+// STRUCT: ImportItem
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+struct ImportItem {
+    path: PathBuf,
+    filename: String,
+    name: String,
+    description: String,
+    hash: u32,
+    size: u64,
+    md5: String,
+}
+
+//------------------------------------------------------------------------------
+// MODULE: ImportDialog
+//------------------------------------------------------------------------------
+mod imp {
+    use super::*;
+
+    //-----------------------------------
+    // Private structure
+    //-----------------------------------
+    #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+    #[properties(wrapper_type = super::ImportDialog)]
+    #[template(resource = "/com/github/DHXS-Launcher/ui/import_dialog.ui")]
+    pub struct ImportDialog {
+        #[template_child]
+        pub(super) back_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) import_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) folder_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) archive_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) empty_page: TemplateChild<adw::StatusPage>,
+        #[template_child]
+        pub(super) found_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub(super) mode_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub(super) conflict_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub(super) done_page: TemplateChild<adw::StatusPage>,
+
+        pub(super) item_rows: RefCell<Vec<(adw::ActionRow, gtk::CheckButton, ImportItem)>>,
+
+        pub(super) scan_id: Cell<u32>,
+        pub(super) staging_folder: RefCell<Option<PathBuf>>,
+
+        #[property(get, set)]
+        target_folder: RefCell<String>,
+    }
+
+    //-----------------------------------
+    // Subclass
+    //-----------------------------------
+    #[glib::object_subclass]
+    impl ObjectSubclass for ImportDialog {
+        const NAME: &'static str = "ImportDialog";
+        type Type = super::ImportDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for ImportDialog {
+        //---------------------------------------
+        // Custom signals
+        //---------------------------------------
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("imported")
+                        .build(),
+                ]
+            })
+        }
+
+        //-----------------------------------
+        // Constructor
+        //-----------------------------------
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.obj().setup_signals();
+        }
+    }
+
+    impl WidgetImpl for ImportDialog {}
+    impl AdwDialogImpl for ImportDialog {
+        //-----------------------------------
+        // Dialog closed handler
+        //-----------------------------------
+        fn closed(&self) {
+            self.obj().reset();
+
+            self.parent_closed();
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// IMPLEMENTATION: ImportDialog
+//------------------------------------------------------------------------------
+glib::wrapper! {
+    pub struct ImportDialog(ObjectSubclass<imp::ImportDialog>)
+        @extends adw::Dialog, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::ShortcutManager;
+}
+
+impl ImportDialog {
+    //-----------------------------------
+    // Setup signals
+    //-----------------------------------
+    fn setup_signals(&self) {
+        let imp = self.imp();
+
+        // Select folder button clicked signal
+        imp.folder_button.connect_clicked(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |_| {
+                let file_dialog = gtk::FileDialog::builder()
+                    .title("Select Folder or Mounted Disc")
+                    .modal(true)
+                    .accept_label("Select")
+                    .build();
+
+                let root = dialog.root()
+                    .and_downcast::<gtk::Window>();
+
+                file_dialog.select_folder(root.as_ref(), None::<&gio::Cancellable>, clone!(
+                    #[weak] dialog,
+                    move |result| {
+                        if let Ok(folder) = result {
+                            dialog.scan_folder(Path::new(&file_to_path(&folder)));
+                        }
+                    }
+                ));
+            }
+        ));
+
+        // Select archive button clicked signal
+        imp.archive_button.connect_clicked(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |_| {
+                let filter = gtk::FileFilter::new();
+                filter.set_name(Some("ZIP Archives"));
+                filter.add_suffix("zip");
+
+                let filters = gio::ListStore::new::<gtk::FileFilter>();
+                filters.append(&filter);
+
+                let file_dialog = gtk::FileDialog::builder()
+                    .title("Select ZIP Archive")
+                    .modal(true)
+                    .accept_label("Select")
+                    .filters(&filters)
+                    .build();
+
+                let root = dialog.root()
+                    .and_downcast::<gtk::Window>();
+
+                file_dialog.open(root.as_ref(), None::<&gio::Cancellable>, clone!(
+                    #[weak] dialog,
+                    move |result| {
+                        if let Ok(file) = result {
+                            dialog.scan_archive(Path::new(&file_to_path(&file)));
+                        }
+                    }
+                ));
+            }
+        ));
+
+        // Back button clicked signal
+        imp.back_button.connect_clicked(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |_| {
+                dialog.reset();
+            }
+        ));
+
+        // Import button clicked signal
+        imp.import_button.connect_clicked(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |_| {
+                dialog.import_selected();
+            }
+        ));
+    }
+
+    //-----------------------------------
+    // Create staging folder helper function
+    //-----------------------------------
+    // Each scan gets a new folder, only removed by the dialog that created it
+    fn create_staging_folder() -> io::Result<PathBuf> {
+        let root = xdg::BaseDirectories::new().create_cache_directory(STAGING_FOLDER)?;
+
+        let folder = root.join(format!("{}-{}", std::process::id(), glib::monotonic_time()));
+
+        fs::create_dir(&folder)?;
+
+        Ok(folder)
+    }
+
+    //-----------------------------------
+    // Is candidate helper function
+    //-----------------------------------
+    // Files that may be game files, by extension or companion file name
+    fn is_candidate(filename: &str) -> bool {
+        let extension = Path::new(filename).extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .unwrap_or_default();
+
+        IWAD_EXTENSIONS.iter().chain(PWAD_RESOURCE_EXTENSIONS.iter())
+            .any(|ext| *ext == extension) ||
+            COMPANION_ARRAY.iter().any(|data| data.filename.eq_ignore_ascii_case(filename))
+    }
+
+    //-----------------------------------
+    // Collect files helper function
+    //-----------------------------------
+    // Symbolic links to folders are not followed
+    fn collect_files(folder: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = fs::read_dir(folder) else { return };
+
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else { continue };
+
+            if file_type.is_dir() {
+                if depth < MAX_SCAN_DEPTH {
+                    Self::collect_files(&entry.path(), depth + 1, files);
+                }
+            } else if entry.file_name().to_str().is_some_and(Self::is_candidate) {
+                files.push(entry.path());
+            }
+        }
+    }
+
+    //-----------------------------------
+    // Identify file helper function
+    //-----------------------------------
+    // Only files identified by the WAD database (or known companion files) are
+    // offered for import
+    fn identify_file(path: &Path) -> Option<ImportItem> {
+        let filename = path.file_name()?.to_str()?.to_owned();
+
+        let hash = crc32(&path.display().to_string()).ok()?;
+
+        // CRC-32 alone can collide, duplicates are found by size and MD5
+        let size = fs::metadata(path).ok()?.len();
+        let md5 = checksum(&path.display().to_string(), glib::ChecksumType::Md5).ok()?;
+
+        let database = WadDatabase::get();
+
+        let (name, description) = if let IWadMatch::Identified(data) = database.identify_iwad(&path.display().to_string(), hash) {
+            (data.name.to_owned(), format!("IWAD \u{2022} {}", data.version))
+        } else if let Some(data) = database.find_pwad(&filename, hash) {
+            (data.name.to_owned(), format!("Add-on \u{2022} {}", data.id.display_name()))
+        } else if let Some(data) = COMPANION_ARRAY.iter().find(|data| data.filename.eq_ignore_ascii_case(&filename)) {
+            (data.name.to_owned(), format!("Companion file \u{2022} {}", data.id.display_name()))
+        } else {
+            return None
+        };
+
+        Some(ImportItem {
+            path: path.to_path_buf(),
+            filename,
+            name,
+            description,
+            hash,
+            size,
+            md5,
+        })
+    }
+
+    //-----------------------------------
+    // Start scan helper function
+    //-----------------------------------
+    // Shows the scanning page and returns the new scan ID (results of previous
+    // scans are discarded)
+    fn start_scan(&self) -> u32 {
+        let imp = self.imp();
+
+        let scan_id = imp.scan_id.get().wrapping_add(1);
+
+        imp.scan_id.set(scan_id);
+
+        imp.stack.set_visible_child_name("scanning");
+
+        imp.back_button.set_visible(true);
+        imp.import_button.set_visible(false);
+
+        scan_id
+    }
+
+    //-----------------------------------
+    // Scan folder function
+    //-----------------------------------
+    // Files are collected and identified in a worker thread
+    fn scan_folder(&self, folder: &Path) {
+        let scan_id = self.start_scan();
+
+        let folder = folder.to_path_buf();
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = dialog)] self,
+            async move {
+                let scan_folder = folder.clone();
+
+                let items = gio::spawn_blocking(move || {
+                    let mut files: Vec<PathBuf> = vec![];
+
+                    Self::collect_files(&scan_folder, 0, &mut files);
+
+                    files.sort_unstable();
+
+                    files.iter()
+                        .filter_map(|path| Self::identify_file(path))
+                        .collect::<Vec<ImportItem>>()
+                })
+                .await
+                .unwrap_or_default();
+
+                let imp = dialog.imp();
+
+                if imp.scan_id.get() != scan_id {
+                    return
+                }
+
+                imp.mode_row.set_sensitive(true);
+
+                dialog.show_items(items, &folder);
+            }
+        ));
+    }
+
+    //-----------------------------------
+    // Extract candidates helper function
+    //-----------------------------------
+    fn extract_candidates(file: &Path, staging: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut archive = ZipArchive::open(file)?;
+
+        let entries: Vec<_> = archive.entries.iter()
+            .filter(|entry| !entry.is_dir() && entry.size <= MAX_ARCHIVE_ENTRY_SIZE)
+            .filter(|entry| {
+                entry.name.rsplit('/').next().is_some_and(Self::is_candidate)
+            })
+            .cloned()
+            .collect();
+
+        let mut files: Vec<PathBuf> = vec![];
+
+        // Each entry gets its own folder (archives may contain files with the same name)
+        for (i, entry) in entries.iter().enumerate() {
+            let Some(name) = entry.name.rsplit('/').next() else { continue };
+
+            let folder = staging.join(i.to_string());
+
+            fs::create_dir_all(&folder)?;
+
+            let path = folder.join(name);
+
+            if archive.extract(entry, &path).is_ok() {
+                files.push(path);
+            }
+        }
+
+        Ok(files)
+    }
+
+    //-----------------------------------
+    // Scan archive function
+    //-----------------------------------
+    // Candidate entries are extracted to a staging folder and identified in a
+    // worker thread
+    fn scan_archive(&self, file: &Path) {
+        let scan_id = self.start_scan();
+
+        let file = file.to_path_buf();
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = dialog)] self,
+            async move {
+                let archive_file = file.clone();
+
+                let result = gio::spawn_blocking(move || -> io::Result<(PathBuf, Vec<ImportItem>)> {
+                    let staging = Self::create_staging_folder()?;
+
+                    match Self::extract_candidates(&archive_file, &staging) {
+                        Ok(files) => {
+                            let items = files.iter()
+                                .filter_map(|path| Self::identify_file(path))
+                                .collect();
+
+                            Ok((staging, items))
+                        },
+                        Err(error) => {
+                            let _ = fs::remove_dir_all(&staging);
+
+                            Err(error)
+                        }
+                    }
+                })
+                .await
+                .unwrap_or_else(|_| Err(io::Error::other("archive scan failed")));
+
+                let imp = dialog.imp();
+
+                if imp.scan_id.get() != scan_id {
+                    if let Ok((staging, _)) = &result {
+                        let _ = fs::remove_dir_all(staging);
+                    }
+
+                    return
+                }
+
+                match result {
+                    Ok((staging, items)) => {
+                        if let Some(previous) = imp.staging_folder.replace(Some(staging)) {
+                            let _ = fs::remove_dir_all(previous);
+                        }
+
+                        // Extracted files are temporary, so they cannot be linked
+                        imp.mode_row.set_selected(0);
+                        imp.mode_row.set_sensitive(false);
+
+                        dialog.show_items(items, &file);
+                    },
+                    Err(error) => {
+                        dialog.show_empty(&format!("Could not read {}: {error}", file.display()));
+                    }
+                }
+            }
+        ));
+    }
+
+    //-----------------------------------
+    // Show empty helper function
+    //-----------------------------------
+    fn show_empty(&self, description: &str) {
+        let imp = self.imp();
+
+        imp.empty_page.set_description(Some(description));
+
+        imp.stack.set_visible_child_name("empty");
+
+        imp.back_button.set_visible(true);
+        imp.import_button.set_visible(false);
+    }
+
+    //-----------------------------------
+    // Show items helper function
+    //-----------------------------------
+    fn show_items(&self, mut items: Vec<ImportItem>, source: &Path) {
+        let imp = self.imp();
+
+        // Remove duplicates (discs often contain the same file several times)
+        items.sort_by(|a, b| (a.size, &a.md5).cmp(&(b.size, &b.md5)));
+        items.dedup_by(|a, b| a.size == b.size && a.md5 == b.md5);
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+
+        if items.is_empty() {
+            self.show_empty(&format!("No known IWADs or add-ons were found in {}", source.display()));
+            return
+        }
+
+        let target = PathBuf::from(self.target_folder());
+
+        let rows: Vec<(adw::ActionRow, gtk::CheckButton, ImportItem)> = items.into_iter()
+            .map(|item| {
+                // Files already present in the IWAD folder are not imported again
+                let installed = fs::read_dir(&target).ok()
+                    .into_iter()
+                    .flat_map(|entries| entries.flatten())
+                    .filter(|entry| entry.file_name().to_string_lossy().eq_ignore_ascii_case(&item.filename))
+                    .any(|entry| crc32(&entry.path().display().to_string()).is_ok_and(|hash| hash == item.hash));
+
+                let check = gtk::CheckButton::builder()
+                    .active(!installed)
+                    .sensitive(!installed)
+                    .valign(gtk::Align::Center)
+                    .build();
+
+                let subtitle = if installed {
+                    format!("{} \u{2022} Already imported", item.description)
+                } else {
+                    item.description.to_owned()
+                };
+
+                let row = adw::ActionRow::builder()
+                    .title(item.name.as_str())
+                    .subtitle(format!("{subtitle}\n{}", item.filename))
+                    .tooltip_text(item.path.display().to_string())
+                    .use_markup(false)
+                    .activatable_widget(&check)
+                    .sensitive(!installed)
+                    .build();
+
+                row.add_prefix(&check);
+
+                check.connect_active_notify(clone!(
+                    #[weak(rename_to = dialog)] self,
+                    move |_| {
+                        dialog.update_import_button();
+                    }
+                ));
+
+                imp.found_group.add(&row);
+
+                (row, check, item)
+            })
+            .collect();
+
+        imp.item_rows.replace(rows);
+
+        imp.found_group.set_description(Some(&source.display().to_string()));
+
+        imp.stack.set_visible_child_name("results");
+
+        imp.back_button.set_visible(true);
+        imp.import_button.set_visible(true);
+
+        self.update_import_button();
+    }
+
+    //-----------------------------------
+    // Update import button helper function
+    //-----------------------------------
+    fn update_import_button(&self) {
+        let imp = self.imp();
+
+        let any_selected = imp.item_rows.borrow().iter()
+            .any(|(_, check, _)| check.is_active() && check.is_sensitive());
+
+        imp.import_button.set_sensitive(any_selected);
+    }
+
+    //-----------------------------------
+    // Import item helper function
+    //-----------------------------------
+    // Returns false if the file was skipped. Files are created under a
+    // temporary name and renamed over the destination once complete, so a
+    // failed import never removes a replaced file.
+    fn import_item(item: &ImportItem, target: &Path, mode: ImportMode, conflict: ConflictMode) -> io::Result<bool> {
+        let mut dest = target.join(&item.filename);
+
+        if fs::symlink_metadata(&dest).is_ok() {
+            // Identical file already present
+            if crc32(&dest.display().to_string()).is_ok_and(|hash| hash == item.hash) {
+                return Ok(false)
+            }
+
+            match conflict {
+                ConflictMode::Skip => return Ok(false),
+                ConflictMode::Replace => {},
+                ConflictMode::KeepBoth => dest = unique_path(&dest),
+            }
+        }
+
+        let temp = target.join(format!(".{}.{TEMP_SUFFIX}", item.filename));
+
+        let _ = fs::remove_file(&temp);
+
+        let result = match mode {
+            ImportMode::Copy => fs::copy(&item.path, &temp).map(|_| ()),
+            ImportMode::Symlink => std::os::unix::fs::symlink(&item.path, &temp),
+        };
+
+        if let Err(error) = result.and_then(|()| fs::rename(&temp, &dest)) {
+            let _ = fs::remove_file(&temp);
+
+            return Err(error)
+        }
+
+        Ok(true)
+    }
+
+    //-----------------------------------
+    // Import selected function
+    //-----------------------------------
+    // Files are copied in a worker thread (IWADs are often read from optical
+    // media)
+    fn import_selected(&self) {
+        let imp = self.imp();
+
+        let target = PathBuf::from(self.target_folder());
+
+        let mode = if imp.mode_row.selected() == 1 { ImportMode::Symlink } else { ImportMode::Copy };
+
+        let conflict = match imp.conflict_row.selected() {
+            1 => ConflictMode::Replace,
+            2 => ConflictMode::KeepBoth,
+            _ => ConflictMode::Skip,
+        };
+
+        let items: Vec<ImportItem> = imp.item_rows.borrow().iter()
+            .filter(|(_, check, _)| check.is_active() && check.is_sensitive())
+            .map(|(_, _, item)| item.clone())
+            .collect();
+
+        let scan_id = imp.scan_id.get();
+
+        imp.stack.set_visible_child_name("importing");
+
+        imp.back_button.set_sensitive(false);
+        imp.import_button.set_visible(false);
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = dialog)] self,
+            async move {
+                let import_target = target.clone();
+
+                let (imported, skipped, errors) = gio::spawn_blocking(move || {
+                    let mut imported = 0;
+                    let mut skipped = 0;
+                    let mut errors: Vec<String> = vec![];
+
+                    if let Err(error) = fs::create_dir_all(&import_target) {
+                        errors.push(format!("{}: {error}", import_target.display()));
+                    } else {
+                        for item in &items {
+                            match Self::import_item(item, &import_target, mode, conflict) {
+                                Ok(true) => imported += 1,
+                                Ok(false) => skipped += 1,
+                                Err(error) => errors.push(format!("{}: {error}", item.filename)),
+                            }
+                        }
+                    }
+
+                    (imported, skipped, errors)
+                })
+                .await
+                .unwrap_or_else(|_| (0, 0, vec![String::from("Import failed")]));
+
+                let imp = dialog.imp();
+
+                imp.back_button.set_sensitive(true);
+
+                // Refresh game list even if the dialog was closed meanwhile
+                if imported > 0 {
+                    dialog.emit_by_name::<()>("imported", &[]);
+                }
+
+                if imp.scan_id.get() != scan_id {
+                    return
+                }
+
+                // Show summary
+                let mut summary = format!("{imported} file(s) imported into {}", target.display());
+
+                if skipped > 0 {
+                    summary.push_str(&format!("\n{skipped} file(s) skipped (already present)"));
+                }
+
+                if !errors.is_empty() {
+                    summary.push_str(&format!("\n\nErrors:\n{}", errors.join("\n")));
+                }
+
+                imp.done_page.set_icon_name(Some(if errors.is_empty() { "emblem-ok-symbolic" } else { "dialog-warning-symbolic" }));
+                imp.done_page.set_description(Some(&summary));
+
+                imp.stack.set_visible_child_name("done");
+            }
+        ));
+    }
+
+    //-----------------------------------
+    // Public reset function
+    //-----------------------------------
+    pub fn reset(&self) {
+        let imp = self.imp();
+
+        for (row, _, _) in imp.item_rows.take() {
+            imp.found_group.remove(&row);
+        }
+
+        // Discard running scans and remove extracted files
+        imp.scan_id.set(imp.scan_id.get().wrapping_add(1));
+
+        if let Some(staging) = imp.staging_folder.take() {
+            let _ = fs::remove_dir_all(staging);
+        }
+
+        imp.stack.set_visible_child_name("start");
+
+        imp.back_button.set_visible(false);
+        imp.import_button.set_visible(false);
+    }
+}
+
+impl Default for ImportDialog {
+    //-----------------------------------
+    // Default constructor
+    //-----------------------------------
+    fn default() -> Self {
+        glib::Object::builder().build()
+    }
+}
@@ -1,15 +1,23 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
 
 use gtk::{gio, glib};
 use adw::subclass::prelude::*;
 use adw::prelude::*;
+use glib::clone;
 
 use glob::{glob_with, MatchOptions};
 
 use crate::iwad_object::IWadObject;
-use crate::iwad_data::{IWadData, IWAD_HASHMAP, IWAD_PATHS};
-use crate::pwad_data::{PWadData, PWAD_HASHMAP};
-use crate::utils::crc32;
+use crate::library_object::{LibraryObject, LibraryStatus};
+use crate::iwad_data::{IWadData, IWadID, IWadMatch, IWAD_EXTENSIONS};
+use crate::iwad_paths::IWadPath;
+use crate::pwad_data::{PWadData, PWAD_RESOURCE_EXTENSIONS};
+use crate::companion_data::find_companions;
+use crate::wad_database::WadDatabase;
+use crate::wad_reader::{WadFile, WadKind};
+use crate::utils::{checksum, crc32};
 
 //------------------------------------------------------------------------------
 // MODULE: IWadComboRow
@@ -20,13 +28,25 @@ mod imp {
     //-----------------------------------
     // Private structure
     //-----------------------------------
-    #[derive(Default, gtk::CompositeTemplate)]
+    #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+    #[properties(wrapper_type = super::IWadComboRow)]
     #[template(resource = "/com/github/DHXS-Launcher/ui/iwad_combo_row.ui")]
     pub struct IWadComboRow {
         #[template_child]
         pub(super) model: TemplateChild<gio::ListStore>,
         #[template_child]
         pub(super) sort_model: TemplateChild<gtk::SortListModel>,
+        #[template_child]
+        pub(super) sorter: TemplateChild<gtk::MultiSorter>,
+        #[template_child]
+        pub(super) preferred_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) warning_image: TemplateChild<gtk::Image>,
+
+        #[property(get, set)]
+        preferred_iwads: RefCell<Vec<String>>,
+
+        pub(super) library: RefCell<Vec<LibraryObject>>,
     }
 
     //-----------------------------------
@@ -49,12 +69,15 @@ mod imp {
         }
     }
 
+    #[glib::derived_properties]
     impl ObjectImpl for IWadComboRow {
         //-----------------------------------
         // Constructor
         //-----------------------------------
         fn constructed(&self) {
             self.parent_constructed();
+
+            self.obj().setup_signals();
         }
     }
 
@@ -75,50 +98,317 @@ glib::wrapper! {
 }
 
 impl IWadComboRow {
+    //-----------------------------------
+    // Setup signals
+    //-----------------------------------
+    fn setup_signals(&self) {
+        let imp = self.imp();
+
+        // Selected item property notify signal
+        self.connect_selected_item_notify(|row| {
+            row.update_preferred_button();
+        });
+
+        // Preferred IWADs property notify signal
+        self.connect_preferred_iwads_notify(|row| {
+            row.update_preferred_iwads();
+        });
+
+        // Preferred button clicked signal
+        imp.preferred_button.connect_clicked(clone!(
+            #[weak(rename_to = row)] self,
+            move |_| {
+                let Some(iwad) = row.selected_iwad() else {
+                    return
+                };
+
+                let key = iwad.selection_key();
+
+                // Only one preferred version per game
+                let other_versions: Vec<String> = row.iwads().iter()
+                    .filter(|other| other.name() == iwad.name())
+                    .map(|other| other.selection_key())
+                    .collect();
+
+                let mut preferred_iwads = row.preferred_iwads();
+
+                preferred_iwads.retain(|preferred| !other_versions.contains(preferred));
+
+                if !iwad.preferred() {
+                    preferred_iwads.push(key);
+                }
+
+                row.set_preferred_iwads(preferred_iwads);
+            }
+        ));
+    }
+
+    //-----------------------------------
+    // IWADs helper function
+    //-----------------------------------
+    fn iwads(&self) -> Vec<IWadObject> {
+        self.imp().model.iter::<IWadObject>()
+            .flatten()
+            .collect()
+    }
+
+    //-----------------------------------
+    // Update preferred IWADs helper function
+    //-----------------------------------
+    fn update_preferred_iwads(&self) {
+        let preferred_iwads = self.preferred_iwads();
+
+        let selected_key = self.selected_iwad().map(|iwad| iwad.selection_key());
+
+        for iwad in self.iwads() {
+            iwad.set_preferred(preferred_iwads.contains(&iwad.selection_key()));
+        }
+
+        // Re-sort list (preferred versions first) and keep selection
+        self.imp().sorter.changed(gtk::SorterChange::Different);
+
+        if let Some(key) = selected_key {
+            self.set_selected_iwad_key(&key);
+        }
+
+        self.update_preferred_button();
+    }
+
+    //-----------------------------------
+    // Update preferred button helper function
+    //-----------------------------------
+    fn update_preferred_button(&self) {
+        let imp = self.imp();
+
+        // Show button if several versions of the selected game exist
+        let selected = self.selected_iwad();
+
+        let has_versions = selected.as_ref().is_some_and(|iwad| {
+            self.iwads().iter()
+                .filter(|other| other.name() == iwad.name())
+                .count() > 1
+        });
+
+        let is_preferred = selected.is_some_and(|iwad| iwad.preferred());
+
+        imp.preferred_button.set_visible(has_versions);
+        imp.preferred_button.set_icon_name(if is_preferred { "starred-symbolic" } else { "non-starred-symbolic" });
+        imp.preferred_button.set_tooltip_text(Some(if is_preferred { "Unmark as Preferred Version" } else { "Mark as Preferred Version" }));
+    }
+
+    //-----------------------------------
+    // Deduplicate helper function
+    //-----------------------------------
+    // Collapses files with identical keys (the group name of the identified
+    // database entry, or size and MD5 for unverified files) into one entry, keeping the file from
+    // the preferred location (or the first in search order). Returns the kept
+    // entries with the other locations of each file.
+    fn deduplicate<T, K: PartialEq>(list: Vec<(T, String, K)>, folders: &[IWadPath], preferred_source: &str) -> Vec<(T, String, Vec<String>)> {
+        let is_preferred = |filename: &str| {
+            folders.iter()
+                .find(|folder| Path::new(filename).parent() == Some(Path::new(&folder.path)))
+                .is_some_and(|folder| folder.source.nick() == preferred_source)
+        };
+
+        let mut entries: Vec<(T, String, K, Vec<String>)> = vec![];
+
+        for (data, filename, key) in list {
+            if let Some(entry) = entries.iter_mut().find(|entry| entry.2 == key) {
+                if is_preferred(&filename) && !is_preferred(&entry.1) {
+                    let previous = std::mem::replace(&mut entry.1, filename);
+                    entry.3.push(previous);
+                } else {
+                    entry.3.push(filename);
+                }
+            } else {
+                entries.push((data, filename, key, vec![]));
+            }
+        }
+
+        entries.into_iter()
+            .map(|(data, filename, _, locations)| (data, filename, locations))
+            .collect()
+    }
+
     //-----------------------------------
     // Public init for folders function
     //-----------------------------------
-    pub fn init_for_folders(&self, user_folder: &str) {
+    pub fn init_for_folders(&self, folders: &[IWadPath], preferred_source: &str) {
         let imp = self.imp();
 
         // Use case-insensitive search
         let options = MatchOptions::default();
 
         // Get list of WAD files in folders
-        let iwad_hashmap = HashMap::from(IWAD_HASHMAP);
-        let pwad_hashmap = HashMap::from(PWAD_HASHMAP);
+        let database = WadDatabase::get();
 
-        let mut iwad_list: Vec<(&IWadData, String)> = vec![];
-        let mut pwad_list: Vec<(&PWadData, String)> = vec![];
+        let mut iwad_list: Vec<(&IWadData, String, String)> = vec![];
+        let mut pwad_list: Vec<(&'static PWadData, String, String)> = vec![];
+        let mut corrupted_list: Vec<(&IWadData, String)> = vec![];
+        let mut unverified_list: Vec<(IWadData, String, String)> = vec![];
+        let mut library_list: Vec<LibraryObject> = vec![];
 
-        for path in IWAD_PATHS.iter().chain([&user_folder])
-            .flat_map(|folder| glob_with(&format!("{folder}/*.wad"), options))
+        for path in folders.iter()
+            .flat_map(|folder| {
+                IWAD_EXTENSIONS.iter().chain(PWAD_RESOURCE_EXTENSIONS.iter())
+                    .map(move |ext| format!("{}/*.{ext}", folder.path))
+            })
+            .flat_map(|pattern| glob_with(&pattern, options))
             .flat_map(|paths| paths.into_iter().flatten())
         {
             let filename = path.display().to_string();
-            
-            if let Ok(hash) = crc32(&filename) {
-                if let Some(data) = iwad_hashmap.get(&hash) {
-                    iwad_list.push((data, filename));
-                } else if let Some(data) = pwad_hashmap.get(&hash) {
-                    pwad_list.push((data, filename));
+
+            let hash = match crc32(&filename) {
+                Ok(hash) => hash,
+                Err(error) => {
+                    library_list.push(LibraryObject::new(&filename, LibraryStatus::Unreadable, &error.to_string(), None));
+                    continue
+                }
+            };
+
+            match database.identify_iwad(&filename, hash) {
+                IWadMatch::Identified(data) => {
+                    library_list.push(LibraryObject::new(&filename, LibraryStatus::IWad, &format!("{} {}", data.name, data.version), Some(hash)));
+
+                    iwad_list.push((data, filename, data.group.clone()));
+                },
+                IWadMatch::Corrupted(data) => {
+                    library_list.push(LibraryObject::new(&filename, LibraryStatus::Corrupted, &format!("Expected {} {}", data.name, data.version), Some(hash)));
+
+                    corrupted_list.push((data, filename));
+                },
+                IWadMatch::Unknown => {
+                    if let Some(data) = database.find_pwad(&filename, hash) {
+                        library_list.push(LibraryObject::new(&filename, LibraryStatus::Addon, &data.name, Some(hash)));
+
+                        pwad_list.push((data, filename, data.group.clone()));
+                    } else if let Some((id, is_bfg)) = IWadID::infer_from_file(&path) {
+                        let version = if is_bfg {
+                            "Unrecognized version (BFG Edition)"
+                        } else {
+                            "Unrecognized version"
+                        };
+
+                        let data = IWadData {
+                            group: String::new(),
+                            id,
+                            name: id.display_name(),
+                            version: version.to_owned(),
+                            crc32: Some(hash),
+                            size: None,
+                            md5: None,
+                            sha1: None,
+                            canonical: false,
+                        };
+
+                        library_list.push(LibraryObject::new(&filename, LibraryStatus::UnknownIWad, &format!("{} (inferred)", data.name), Some(hash)));
+
+                        // CRC-32 alone can collide, compare size and MD5 (files
+                        // that cannot be read are only equal to themselves)
+                        let key = fs::metadata(&path).ok()
+                            .zip(checksum(&filename, glib::ChecksumType::Md5).ok())
+                            .map_or_else(|| filename.clone(), |(metadata, md5)| format!("{} {md5}", metadata.len()));
+
+                        unverified_list.push((data, filename, key));
+                    } else {
+                        let status = match WadFile::open(&path).map(|wad| wad.kind) {
+                            Ok(WadKind::IWad) => LibraryStatus::UnknownIWad,
+                            Ok(WadKind::PWad) => LibraryStatus::PWad,
+                            Err(_) => LibraryStatus::Unknown
+                        };
+
+                        library_list.push(LibraryObject::new(&filename, status, "", Some(hash)));
+                    }
                 }
             }
         }
 
-        // Add IWADs to combo row
-        let iwad_objects = iwad_list.into_iter()
-            .map(|(iwad_data, filename)| {
-                let pwad_files: Vec<&str> = pwad_list.iter()
-                    .filter(|(pwad_data, _)| pwad_data.id == iwad_data.id)
-                    .map(|(_, filename)| filename.as_str())
-                    .collect();
+        imp.library.replace(library_list);
 
-                IWadObject::new(iwad_data, &filename, &pwad_files)
+        // Show warning for corrupted IWADs
+        let warning = corrupted_list.iter()
+            .map(|(data, filename)| {
+                format!("<b>{}</b>\n{} {}", glib::markup_escape_text(filename), glib::markup_escape_text(&data.name), glib::markup_escape_text(&data.version))
             })
-            .collect::<Vec<IWadObject>>();
+            .collect::<Vec<String>>()
+            .join("\n\n");
+
+        imp.warning_image.set_visible(!corrupted_list.is_empty());
+        imp.warning_image.set_tooltip_markup(Some(&format!("Possibly corrupted IWAD files (checksum mismatch):\n\n{warning}\n\nSee the WAD Library for details.")));
+
+        // Collapse identical files found in several locations
+        let iwad_list = Self::deduplicate(iwad_list, folders, preferred_source);
+        let unverified_list = Self::deduplicate(unverified_list, folders, preferred_source);
+
+        let pwad_list: Vec<(&'static PWadData, String)> = Self::deduplicate(pwad_list, folders, preferred_source).into_iter()
+            .map(|(data, filename, _)| (data, filename))
+            .collect();
+
+        // Add IWADs to combo row (unverified IWADs are inferred from their lumps)
+        let preferred_iwads = self.preferred_iwads();
+
+        let mut iwad_objects: Vec<IWadObject> = vec![];
+
+        for (iwad_data, filename, locations, unverified) in iwad_list.iter()
+            .map(|(iwad_data, filename, locations)| (*iwad_data, filename, locations, false))
+            .chain(unverified_list.iter().map(|(iwad_data, filename, locations)| (iwad_data, filename, locations, true)))
+        {
+            let (expansions, optional_pwads): (Vec<_>, Vec<_>) = pwad_list.iter()
+                .filter(|(pwad_data, _)| pwad_data.id.intersects(iwad_data.id))
+                .cloned()
+                .partition(|(pwad_data, _)| pwad_data.is_expansion());
+
+            // Get tooltip with file location and alternative locations
+            let tooltip = if locations.is_empty() {
+                filename.to_owned()
+            } else {
+                format!("{filename}\n\nAlso found in:\n{}", locations.join("\n"))
+            };
+
+            // Get folder source (user folder, store, ...)
+            let source = folders.iter()
+                .find(|folder| Path::new(filename).parent() == Some(Path::new(&folder.path)))
+                .map(|folder| folder.source.display_name())
+                .unwrap_or_default();
+
+            // Get companion files
+            let companions = find_companions(iwad_data.id, Path::new(filename));
+
+            // Add IWAD and its expansions (listed as separate games)
+            let iwad = IWadObject::new(iwad_data, filename);
+
+            let expansion_iwads = expansions.iter()
+                .map(|(pwad_data, pwad_file)| {
+                    let obj = IWadObject::new_expansion(iwad_data, filename, &pwad_data.name, pwad_file);
+                    obj.set_warp(pwad_data.warp.as_deref());
+                    obj.set_requires(pwad_data.requires);
+
+                    obj
+                });
+
+            for obj in std::iter::once(iwad).chain(expansion_iwads) {
+                obj.set_unverified(unverified);
+                obj.set_source(source.as_str());
+                obj.set_tooltip(tooltip.as_str());
+                obj.set_optional_pwads(optional_pwads.clone());
+                obj.set_companions(companions.clone());
+                obj.set_preferred(preferred_iwads.contains(&obj.selection_key()));
+
+                iwad_objects.push(obj);
+            }
+        }
 
         imp.model.splice(0, imp.model.n_items(), &iwad_objects);
+
+        self.update_preferred_button();
+    }
+
+    //-----------------------------------
+    // Public library function
+    //-----------------------------------
+    // Every file found by the last scan, with its identification status
+    pub fn library(&self) -> Vec<LibraryObject> {
+        self.imp().library.borrow().clone()
     }
 
     //-----------------------------------
@@ -130,16 +420,32 @@ impl IWadComboRow {
     }
 
     //-----------------------------------
-    // Public set selected iwad file function
+    // Public compatible iwad function
     //-----------------------------------
-    pub fn set_selected_iwad_file(&self, filename: &str) {
+    // First listed IWAD for one of the given games (base games before expansions)
+    pub fn compatible_iwad(&self, games: IWadID) -> Option<IWadObject> {
+        let iwads: Vec<IWadObject> = self.imp().sort_model.iter::<IWadObject>()
+            .flatten()
+            .filter(|iwad| games.contains(iwad.id()))
+            .collect();
+
+        iwads.iter()
+            .find(|iwad| iwad.expansion_file().is_none())
+            .or_else(|| iwads.first())
+            .cloned()
+    }
+
+    //-----------------------------------
+    // Public set selected iwad key function
+    //-----------------------------------
+    pub fn set_selected_iwad_key(&self, key: &str) {
         let index = self.imp().sort_model.iter::<glib::Object>()
             .flatten()
             .position(|obj| {
                 let iwad = obj.downcast::<IWadObject>()
                     .expect("Must be a 'IWadObject'");
 
-                iwad.filename() == filename
+                iwad.selection_key() == key
             });
 
         self.set_selected(index.unwrap_or_default() as u32);
@@ -154,3 +460,56 @@ impl Default for IWadComboRow {
         glib::Object::builder().build()
     }
 }
+
+//------------------------------------------------------------------------------
+// TESTS
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::iwad_paths::IWadPathSource;
+
+    fn iwad_data(group: &str, id: IWadID, name: &str, md5: &str) -> IWadData {
+        IWadData {
+            group: group.to_owned(),
+            id,
+            name: name.to_owned(),
+            version: String::from("v0.11.3"),
+            crc32: Some(0x81901f03),
+            size: None,
+            md5: Some(md5.to_owned()),
+            sha1: None,
+            canonical: false,
+        }
+    }
+
+    #[test]
+    fn entries_sharing_crc_are_kept_apart() {
+        let phase1 = iwad_data("IWAD freedoom1", IWadID::FREEDOOM1, "FreeDoom: Phase 1", "ea471a3d38fcee0fb3a69bcd3221e335");
+        let phase2 = iwad_data("IWAD freedoom2", IWadID::FREEDOOM2, "FreeDoom: Phase 2", "984f99af08f085e38070f51095ab7c31");
+
+        let folders = [
+            IWadPath { path: String::from("/user"), source: IWadPathSource::User },
+            IWadPath { path: String::from("/steam"), source: IWadPathSource::Steam },
+        ];
+
+        let list = vec![
+            (&phase1, String::from("/user/freedoom1.wad"), phase1.group.clone()),
+            (&phase2, String::from("/user/freedoom2.wad"), phase2.group.clone()),
+            (&phase2, String::from("/steam/freedoom2.wad"), phase2.group.clone()),
+        ];
+
+        let entries = IWadComboRow::deduplicate(list, &folders, &IWadPathSource::Steam.nick());
+
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0].0.id, IWadID::FREEDOOM1);
+        assert_eq!(entries[0].1, "/user/freedoom1.wad");
+        assert!(entries[0].2.is_empty());
+
+        assert_eq!(entries[1].0.id, IWadID::FREEDOOM2);
+        assert_eq!(entries[1].1, "/steam/freedoom2.wad");
+        assert_eq!(entries[1].2, vec![String::from("/user/freedoom2.wad")]);
+    }
+}
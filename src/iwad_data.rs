@@ -1,14 +1,31 @@
+use std::path::Path;
+
 use gtk::glib;
+use gtk::prelude::ToValue;
+
+use crate::wad_reader::{WadFile, WadKind};
 
 //------------------------------------------------------------------------------
 // CONST VARIABLES
 //------------------------------------------------------------------------------
-pub const IWAD_PATHS: [&str; 5] = [
-    "/usr/share/doom",
-    "/usr/share/games/doom",
-    "/usr/share/games/heretic",
-    "/usr/share/games/hexen",
-    "/usr/share/games/strife",
+// File extensions of IWAD files
+pub const IWAD_EXTENSIONS: [&str; 3] = ["wad", "ipk3", "pk3"];
+
+// Well-known IWAD file names that cannot be told apart by their lumps (also
+// the only way to recognize games without checksums in the WAD database)
+pub const IWAD_FILENAMES: [(&str, IWadID); 12] = [
+    ("plutonia.wad", IWadID::PLUTONIA),
+    ("tnt.wad", IWadID::TNT),
+    ("chex3.wad", IWadID::CHEX3),
+    ("harm1.wad", IWadID::HARMONY),
+    ("rekkr.wad", IWadID::REKKR),
+    ("rekkrsa.wad", IWadID::REKKR),
+    ("square1.pk3", IWadID::SQUARE),
+    ("blasphem.wad", IWadID::BLASPHEMER),
+    ("blasphemer.wad", IWadID::BLASPHEMER),
+    ("action2.wad", IWadID::ACTION2),
+    ("hedon.ipk3", IWadID::HEDON),
+    ("hedon.wad", IWadID::HEDON),
 ];
 
 //------------------------------------------------------------------------------
@@ -17,36 +34,56 @@ pub const IWAD_PATHS: [&str; 5] = [
 #[glib::flags(name = "IWadID")]
 pub enum IWadID {
     #[flags_value(name = "Doom")]
-    DOOM          = 0b0000_0000_0000_0001,
+    DOOM          = 0b0000_0000_0000_0000_0001,
     #[flags_value(name = "Doom")]
-    UDOOM         = 0b0000_0000_0000_0010,
+    UDOOM         = 0b0000_0000_0000_0000_0010,
     #[flags_value(name = "Doom II")]
-    DOOM2         = 0b0000_0000_0000_0100,
+    DOOM2         = 0b0000_0000_0000_0000_0100,
     #[flags_value(name = "Final Doom")]
-    PLUTONIA      = 0b0000_0000_0000_1000,
+    PLUTONIA      = 0b0000_0000_0000_0000_1000,
     #[flags_value(name = "Final Doom")]
-    TNT           = 0b0000_0000_0001_0000,
+    TNT           = 0b0000_0000_0000_0001_0000,
     #[flags_value(name = "FreeDoom")]
-    FREEDOOM1     = 0b0000_0000_0010_0000,
+    FREEDOOM1     = 0b0000_0000_0000_0010_0000,
     #[flags_value(name = "FreeDoom")]
-    FREEDOOM2     = 0b0000_0000_0100_0000,
+    FREEDOOM2     = 0b0000_0000_0000_0100_0000,
     #[flags_value(name = "Heretic")]
-    HERETIC       = 0b0000_0000_1000_0000,
+    HERETIC       = 0b0000_0000_0000_1000_0000,
     #[flags_value(name = "Hexen")]
-    HEXEN         = 0b0000_0001_0000_0000,
+    HEXEN         = 0b0000_0000_0001_0000_0000,
     #[flags_value(name = "Strife")]
-    STRIFE        = 0b0000_0010_0000_0000,
+    STRIFE        = 0b0000_0000_0010_0000_0000,
     #[flags_value(name = "HacX")]
-    HACX          = 0b0000_0100_0000_0000,
+    HACX          = 0b0000_0000_0100_0000_0000,
+    #[flags_value(name = "Chex Quest")]
+    CHEX          = 0b0000_0000_1000_0000_0000,
+    #[flags_value(name = "Chex Quest 3")]
+    CHEX3         = 0b0000_0001_0000_0000_0000,
+    #[flags_value(name = "Harmony")]
+    HARMONY       = 0b0000_0010_0000_0000_0000,
+    #[flags_value(name = "REKKR")]
+    REKKR         = 0b0000_0100_0000_0000_0000,
+    #[flags_value(name = "The Adventures of Square")]
+    SQUARE        = 0b0000_1000_0000_0000_0000,
+    #[flags_value(name = "Blasphemer")]
+    BLASPHEMER    = 0b0001_0000_0000_0000_0000,
+    #[flags_value(name = "Action Doom 2")]
+    ACTION2       = 0b0010_0000_0000_0000_0000,
+    #[flags_value(name = "Hedon")]
+    HEDON         = 0b0100_0000_0000_0000_0000,
 
     #[flags_value(skip)]
     ANY_DOOM = Self::DOOM.bits() | Self::UDOOM.bits() | Self::DOOM2.bits() | Self::PLUTONIA.bits() | Self::TNT.bits() | Self::FREEDOOM1.bits() | Self::FREEDOOM2.bits(),
     #[flags_value(skip)]
     ANY_DOOM_HACX = Self::ANY_DOOM.bits() | Self::HACX.bits(),
     #[flags_value(skip)]
+    ANY_HERETIC = Self::HERETIC.bits() | Self::BLASPHEMER.bits(),
+    #[flags_value(skip)]
     ALL_NO_STRIFE_HACX = Self::ANY_DOOM.bits() | Self::HERETIC.bits() | Self::HEXEN.bits(),
     #[flags_value(skip)]
-    ALL = Self::ALL_NO_STRIFE_HACX.bits() | Self::STRIFE.bits() | Self::HACX.bits(),
+    ALL_ORIGINAL = Self::ALL_NO_STRIFE_HACX.bits() | Self::STRIFE.bits() | Self::HACX.bits(),
+    #[flags_value(skip)]
+    ALL = Self::ALL_ORIGINAL.bits() | Self::CHEX.bits() | Self::CHEX3.bits() | Self::HARMONY.bits() | Self::REKKR.bits() | Self::SQUARE.bits() | Self::BLASPHEMER.bits() | Self::ACTION2.bits() | Self::HEDON.bits(),
 }
 
 impl Default for IWadID {
@@ -55,250 +92,154 @@ impl Default for IWadID {
     }
 }
 
+impl IWadID {
+    //-----------------------------------
+    // Display name function
+    //-----------------------------------
+    pub fn display_name(self) -> String {
+        glib::FlagsValue::from_value(&self.to_value())
+            .and_then(|(_, values)| values.first().map(|value| value.name().to_owned()))
+            .unwrap_or_default()
+    }
+
+    //-----------------------------------
+    // Display names function
+    //-----------------------------------
+    // Sorted names of all games (games sharing a name are listed once)
+    pub fn display_names(self) -> Vec<String> {
+        let mut names: Vec<String> = glib::FlagsValue::from_value(&self.to_value())
+            .map(|(_, values)| values.iter().map(|value| value.name().to_owned()).collect())
+            .unwrap_or_default();
+
+        names.sort_unstable();
+        names.dedup();
+
+        names
+    }
+
+    //-----------------------------------
+    // Infer from file function
+    //-----------------------------------
+    // Guesses the game of an unrecognized IWAD from its file name and lumps;
+    // the result is unverified. Returns the game and whether the IWAD looks
+    // like a BFG Edition one.
+    pub fn infer_from_file(path: &Path) -> Option<(Self, bool)> {
+        let filename = path.file_name()?.to_str()?.to_lowercase();
+
+        let wad = WadFile::open(path).ok();
+
+        // Only IWADs can be inferred from lumps (PK3 IWADs only by file name)
+        if wad.as_ref().is_some_and(|wad| wad.kind != WadKind::IWad) {
+            return None
+        }
+
+        let is_bfg = wad.as_ref().is_some_and(|wad| wad.has_lump("DMENUPIC"));
+
+        let by_filename = IWAD_FILENAMES.iter()
+            .find(|(name, _)| *name == filename)
+            .map(|(_, id)| *id);
+
+        if by_filename.is_some() {
+            return by_filename.map(|id| (id, is_bfg))
+        }
+
+        let wad = wad?;
+
+        let id = if wad.has_lump("FREEDOOM") {
+            if wad.has_lump("E1M1") { Self::FREEDOOM1 } else { Self::FREEDOOM2 }
+        } else if wad.has_lump("W94_1") {
+            Self::CHEX
+        } else if wad.has_lump("HACX-R") {
+            Self::HACX
+        } else if wad.has_lump("SCRIPT00") {
+            Self::STRIFE
+        } else if wad.has_lump("BEHAVIOR") {
+            Self::HEXEN
+        } else if wad.has_lump("MUS_E1M1") {
+            Self::HERETIC
+        } else if wad.has_lump("E1M1") {
+            // Ultimate Doom adds a fourth episode
+            if wad.has_lump("E4M1") { Self::UDOOM } else { Self::DOOM }
+        } else if wad.has_lump("MAP01") {
+            Self::DOOM2
+        } else {
+            return None
+        };
+
+        Some((id, is_bfg))
+    }
+
+    //-----------------------------------
+    // Nick function
+    //-----------------------------------
+    // Unique name of a single game (games may share display names)
+    pub fn nick(self) -> String {
+        glib::FlagsValue::from_value(&self.to_value())
+            .and_then(|(_, values)| values.first().map(|value| value.nick().to_owned()))
+            .unwrap_or_default()
+    }
+
+    //-----------------------------------
+    // Keys function
+    //-----------------------------------
+    // Keys of all games, as parsed by from_key()
+    pub fn keys(self) -> Vec<String> {
+        self.iter_names()
+            .map(|(name, _)| name.to_owned())
+            .collect()
+    }
+
+    //-----------------------------------
+    // From key function
+    //-----------------------------------
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "DOOM" => Some(Self::DOOM),
+            "UDOOM" => Some(Self::UDOOM),
+            "DOOM2" => Some(Self::DOOM2),
+            "PLUTONIA" => Some(Self::PLUTONIA),
+            "TNT" => Some(Self::TNT),
+            "FREEDOOM1" => Some(Self::FREEDOOM1),
+            "FREEDOOM2" => Some(Self::FREEDOOM2),
+            "HERETIC" => Some(Self::HERETIC),
+            "HEXEN" => Some(Self::HEXEN),
+            "STRIFE" => Some(Self::STRIFE),
+            "HACX" => Some(Self::HACX),
+            "CHEX" => Some(Self::CHEX),
+            "CHEX3" => Some(Self::CHEX3),
+            "HARMONY" => Some(Self::HARMONY),
+            "REKKR" => Some(Self::REKKR),
+            "SQUARE" => Some(Self::SQUARE),
+            "BLASPHEMER" => Some(Self::BLASPHEMER),
+            "ACTION2" => Some(Self::ACTION2),
+            "HEDON" => Some(Self::HEDON),
+            _ => None
+        }
+    }
+}
+
 //------------------------------------------------------------------------------
 // STRUCT: IWadData
 //------------------------------------------------------------------------------
 #[derive(Debug)]
-pub struct IWadData<'a> {
+pub struct IWadData {
+    pub group: String,
     pub id: IWadID,
-    pub name: &'a str,
-    pub version: &'a str,
+    pub name: String,
+    pub version: String,
+    pub crc32: Option<u32>,
+    pub size: Option<u64>,
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    pub canonical: bool,
 }
 
 //------------------------------------------------------------------------------
-// IWAD DATA
+// ENUM: IWadMatch
 //------------------------------------------------------------------------------
-pub const IWAD_HASHMAP: [(u32, IWadData); 56] = [
-    // (ULTIMATE) DOOM ---------------------------------------------------------
-    (
-        0x66457ab9,
-        IWadData { id: IWadID::DOOM, name: "Doom", version: "v1.1" }
-    ),
-    (
-        0xa5da8930,
-        IWadData { id: IWadID::DOOM, name: "Doom", version: "v1.2" }
-    ),
-    (
-        0xf756aab5,
-        IWadData { id: IWadID::DOOM, name: "Doom", version: "v1.666" }
-    ),
-    (
-        0x8d242df9,
-        IWadData { id: IWadID::DOOM, name: "Doom", version: "v1.8" }
-    ),
-    (
-        0x723e60f9,
-        IWadData { id: IWadID::DOOM, name: "Doom", version: "v1.9" }
-    ),
-    (
-        0xbf0eaac0,
-        IWadData { id: IWadID::UDOOM, name: "Doom - The Ultimate Doom", version: "v1.9ud" }
-    ),
-    (
-        0x5efa677e,
-        IWadData { id: IWadID::UDOOM, name: "Doom - The Ultimate Doom", version: "v1.9ud (BFG Edition)" }
-    ),
-    (
-        0x75c3b7bf,
-        IWadData { id: IWadID::UDOOM, name: "Doom - The Ultimate Doom", version: "v1.9ud (Doom I Enhanced)" }
-    ),
-    (
-        0xcff03d9f,
-        IWadData { id: IWadID::UDOOM, name: "Doom - The Ultimate Doom", version: "Doom + Doom II" }
-    ),
-    (
-        0xd5f8c089,
-        IWadData { id: IWadID::UDOOM, name: "Doom - The Ultimate Doom", version: "Doom + Doom II" }
-    ),
-    // DOOM2 -------------------------------------------------------------------
-    (
-        0xc08005f7,
-        IWadData { id: IWadID::DOOM2, name: "Doom II", version: "v1.666 (German)" }
-    ),
-    (
-        0xe2a683bd,
-        IWadData { id: IWadID::DOOM2, name: "Doom II", version: "v1.666" }
-    ),
-    (
-        0x47daeb2e,
-        IWadData { id: IWadID::DOOM2, name: "Doom II", version: "v1.7" }
-    ),
-    (
-        0x952f6baa,
-        IWadData { id: IWadID::DOOM2, name: "Doom II", version: "v1.7a" }
-    ),
-    (
-        0x27eaae69,
-        IWadData { id: IWadID::DOOM2, name: "Doom II", version: "v1.8 (French)" }
-    ),
-    (
-        0x31bd3bc0,
-        IWadData { id: IWadID::DOOM2, name: "Doom II", version: "v1.8" }
-    ),
-    (
-        0xec8725db,
-        IWadData { id: IWadID::DOOM2, name: "Doom II", version: "v1.9" }
-    ),
-    (
-        0xdbaa4a2b,
-        IWadData { id: IWadID::DOOM2, name: "Doom II", version: "v1.9 (PC-98)" }
-    ),
-    (
-        0x927a778a,
-        IWadData { id: IWadID::DOOM2, name: "Doom II", version: "v1.9 (BFG Edition)" }
-    ),
-    (
-        0xf1d1ad55,
-        IWadData { id: IWadID::DOOM2, name: "Doom II", version: "v1.9 (Doom II Enhanced)" }
-    ),
-    (
-        0x09b8a6ae,
-        IWadData { id: IWadID::DOOM2, name: "Doom II", version: "Doom + Doom II" }
-    ),
-    (
-        0x151b8a96,
-        IWadData { id: IWadID::DOOM2, name: "Doom II", version: "Doom + Doom II" }
-    ),
-    // FINAL DOOM --------------------------------------------------------------
-    (
-        0x48d1453c,
-        IWadData { id: IWadID::PLUTONIA, name: "Final Doom - The Plutonia Experiment", version: "v1.9" }
-    ),
-    (
-        0x15cd1448,
-        IWadData { id: IWadID::PLUTONIA, name: "Final Doom - The Plutonia Experiment", version: "v1.9 (Fixed)" }
-    ),
-    (
-        0x903dcc27,
-        IWadData { id: IWadID::TNT, name: "Final Doom - TNT: Evilution", version: "v1.9" }
-    ),
-    (
-        0xd4bb05c0,
-        IWadData { id: IWadID::TNT, name: "Final Doom - TNT: Evilution", version: "v1.9 (Fixed)" }
-    ),
-    // FREEDOOM ----------------------------------------------------------------
-    (
-        0x562d477f,
-        IWadData { id: IWadID::FREEDOOM1, name: "FreeDoom: Phase 1", version: "v0.10.0" }
-    ),
-    (
-        0xd94b737d,
-        IWadData { id: IWadID::FREEDOOM1, name: "FreeDoom: Phase 1", version: "v0.10.1" }
-    ),
-    (
-        0x48aef988,
-        IWadData { id: IWadID::FREEDOOM1, name: "FreeDoom: Phase 1", version: "v0.11.0" }
-    ),
-    (
-        0x872e5d9d,
-        IWadData { id: IWadID::FREEDOOM1, name: "FreeDoom: Phase 1", version: "v0.11.1" }
-    ),
-    (
-        0x922cc8c4,
-        IWadData { id: IWadID::FREEDOOM1, name: "FreeDoom: Phase 1", version: "v0.11.2" }
-    ),
-    (
-        0x81901f03,
-        IWadData { id: IWadID::FREEDOOM1, name: "FreeDoom: Phase 1", version: "v0.11.3" }
-    ),
-    (
-        0x070682b7,
-        IWadData { id: IWadID::FREEDOOM1, name: "FreeDoom: Phase 1", version: "v0.12.0" }
-    ),
-    (
-        0xde6ddb27,
-        IWadData { id: IWadID::FREEDOOM1, name: "FreeDoom: Phase 1", version: "v0.12.1" }
-    ),
-    (
-        0xe42df22f,
-        IWadData { id: IWadID::FREEDOOM1, name: "FreeDoom: Phase 1", version: "v0.13.0" }
-    ),
-    (
-        0xfd3019dc,
-        IWadData { id: IWadID::FREEDOOM2, name: "FreeDoom: Phase 2", version: "v0.10.0" }
-    ),
-    (
-        0xbc18778d,
-        IWadData { id: IWadID::FREEDOOM2, name: "FreeDoom: Phase 2", version: "v0.10.1" }
-    ),
-    (
-        0x23997426,
-        IWadData { id: IWadID::FREEDOOM2, name: "FreeDoom: Phase 2", version: "v0.11.0" }
-    ),
-    (
-        0xbbcfea9b,
-        IWadData { id: IWadID::FREEDOOM2, name: "FreeDoom: Phase 2", version: "v0.11.1" }
-    ),
-    (
-        0xa758c437,
-        IWadData { id: IWadID::FREEDOOM2, name: "FreeDoom: Phase 2", version: "v0.11.2" }
-    ),
-    (
-        0x81901f03,
-        IWadData { id: IWadID::FREEDOOM2, name: "FreeDoom: Phase 2", version: "v0.11.3" }
-    ),
-    (
-        0xb66d9e8d,
-        IWadData { id: IWadID::FREEDOOM2, name: "FreeDoom: Phase 2", version: "v0.12.0" }
-    ),
-    (
-        0x212e1cf9,
-        IWadData { id: IWadID::FREEDOOM2, name: "FreeDoom: Phase 2", version: "v0.12.1" }
-    ),
-    (
-        0xa0bfeb53,
-        IWadData { id: IWadID::FREEDOOM2, name: "FreeDoom: Phase 2", version: "v0.13.0" }
-    ),
-    // HACX --------------------------------------------------------------------
-    (
-        0x4f37c580,
-        IWadData { id: IWadID::HACX, name: "HacX", version: "v1.0" }
-    ),
-    (
-        0xb95a03d2,
-        IWadData { id: IWadID::HACX, name: "HacX", version: "v1.1" }
-    ),
-    (
-        0x72e3b8ac,
-        IWadData { id: IWadID::HACX, name: "HacX", version: "v1.2" }
-    ),
-    (
-        0x80b881c3,
-        IWadData { id: IWadID::HACX, name: "HacX", version: "v2.0r58" }
-    ),
-    (
-        0x19d1bb98,
-        IWadData { id: IWadID::HACX, name: "HacX", version: "v2.0r61" }
-    ),
-    // HERETIC -----------------------------------------------------------------
-    (
-        0x77482d1e,
-        IWadData { id: IWadID::HERETIC, name: "Heretic", version: "v1.0" }
-    ),
-    (
-        0x54759180,
-        IWadData { id: IWadID::HERETIC, name: "Heretic", version: "v1.2" }
-    ),
-    (
-        0x5b16049e,
-        IWadData { id: IWadID::HERETIC, name: "Heretic: Shadow of the Serpent Riders", version: "v1.3" }
-    ),
-    // HEXEN -------------------------------------------------------------------
-    (
-        0xeece0236,
-        IWadData { id: IWadID::HEXEN, name: "HeXen: Beyond Heretic", version: "v1.0" }
-    ),
-    (
-        0xdca9114c,
-        IWadData { id: IWadID::HEXEN, name: "HeXen: Beyond Heretic", version: "v1.1" }
-    ),
-    // STRIFE ------------------------------------------------------------------
-    (
-        0xb7581abd,
-        IWadData { id: IWadID::STRIFE, name: "Strife", version: "v1.1" }
-    ),
-    (
-        0x4234ace5,
-        IWadData { id: IWadID::STRIFE, name: "Strife", version: "v1.2-1.31" }
-    ),
-];
+#[derive(Debug)]
+pub enum IWadMatch<'a> {
+    Identified(&'a IWadData),
+    Corrupted(&'a IWadData),
+    Unknown,
+}
@@ -1,10 +1,14 @@
 use std::cell::{Cell, RefCell};
+use std::path::Path;
 
 use gtk::glib;
 use gtk::subclass::prelude::*;
 use gtk::prelude::ObjectExt;
 
+use crate::engine_data::EngineFeatures;
 use crate::iwad_data::{IWadData, IWadID};
+use crate::pwad_data::PWadData;
+use crate::companion_data::CompanionData;
 
 //------------------------------------------------------------------------------
 // MODULE: IWadObject
@@ -27,7 +31,22 @@ mod imp {
         #[property(get, set)]
         filename: RefCell<String>,
         #[property(get, set)]
-        pwad_files: RefCell<Vec<String>>,
+        unverified: Cell<bool>,
+        #[property(get, set)]
+        source: RefCell<String>,
+        #[property(get, set, nullable)]
+        expansion_file: RefCell<Option<String>>,
+        #[property(get, set, nullable)]
+        warp: RefCell<Option<String>>,
+        #[property(get, set)]
+        requires: Cell<EngineFeatures>,
+        #[property(get, set)]
+        tooltip: RefCell<String>,
+        #[property(get, set)]
+        preferred: Cell<bool>,
+
+        pub(super) optional_pwads: RefCell<Vec<(&'static PWadData, String)>>,
+        pub(super) companions: RefCell<Vec<(&'static CompanionData, String)>>,
     }
 
     //-----------------------------------
@@ -56,14 +75,77 @@ impl IWadObject {
     //-----------------------------------
     // New function
     //-----------------------------------
-    pub fn new(data: &IWadData, filename: &str, pwad_files: &[&str]) -> Self {
+    pub fn new(data: &IWadData, filename: &str) -> Self {
         // Build IWadObject
         glib::Object::builder()
             .property("id", data.id)
-            .property("name", data.name)
-            .property("version", data.version)
+            .property("name", data.name.as_str())
+            .property("version", data.version.as_str())
+            .property("filename", filename)
+            .build()
+    }
+
+    //-----------------------------------
+    // New expansion function
+    //-----------------------------------
+    pub fn new_expansion(data: &IWadData, filename: &str, expansion_name: &str, expansion_file: &str) -> Self {
+        // Build IWadObject for expansion (base IWAD plus expansion file)
+        glib::Object::builder()
+            .property("id", data.id)
+            .property("name", format!("{}: {}", data.name, expansion_name))
+            .property("version", data.version.as_str())
             .property("filename", filename)
-            .property("pwad-files", pwad_files)
+            .property("expansion-file", expansion_file)
             .build()
     }
+
+    //-----------------------------------
+    // Public selection key function
+    //-----------------------------------
+    // Unique key for the entry (expansions share the IWAD file name)
+    pub fn selection_key(&self) -> String {
+        let filename = self.filename();
+
+        self.expansion_file()
+            .map_or(filename.clone(), |expansion_file| format!("{filename}|{expansion_file}"))
+    }
+
+    //-----------------------------------
+    // Public game key function
+    //-----------------------------------
+    // Key shared by every copy of a game (expansions add their file name)
+    pub fn game_key(&self) -> String {
+        let id = self.id().nick();
+
+        self.expansion_file()
+            .and_then(|expansion_file| {
+                Path::new(&expansion_file).file_name()
+                    .map(|name| name.to_string_lossy().to_lowercase())
+            })
+            .map_or(id.clone(), |name| format!("{id}|{name}"))
+    }
+
+    //-----------------------------------
+    // Public optional PWADs functions
+    //-----------------------------------
+    // Add-on and resource PWADs found for the IWAD
+    pub fn optional_pwads(&self) -> Vec<(&'static PWadData, String)> {
+        self.imp().optional_pwads.borrow().clone()
+    }
+
+    pub fn set_optional_pwads(&self, pwads: Vec<(&'static PWadData, String)>) {
+        self.imp().optional_pwads.replace(pwads);
+    }
+
+    //-----------------------------------
+    // Public companions functions
+    //-----------------------------------
+    // Companion files found next to the IWAD (DEHACKED patches, voices, ...)
+    pub fn companions(&self) -> Vec<(&'static CompanionData, String)> {
+        self.imp().companions.borrow().clone()
+    }
+
+    pub fn set_companions(&self, companions: Vec<(&'static CompanionData, String)>) {
+        self.imp().companions.replace(companions);
+    }
 }
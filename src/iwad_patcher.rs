@@ -0,0 +1,183 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::iwad_data::{IWadData, IWadMatch};
+use crate::utils::{crc32, unique_path};
+use crate::wad_database::WadDatabase;
+
+//------------------------------------------------------------------------------
+// CONST VARIABLES
+//------------------------------------------------------------------------------
+// User folder searched for patch files
+pub const PATCHES_FOLDER: &str = "dhxs-launcher/patches";
+
+const TEMP_SUFFIX: &str = "patching";
+
+//------------------------------------------------------------------------------
+// ENUM: PatchFormat
+//------------------------------------------------------------------------------
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum PatchFormat {
+    XDelta,
+    BsDiff,
+}
+
+impl PatchFormat {
+    //-----------------------------------
+    // From path function
+    //-----------------------------------
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+
+        match extension.as_str() {
+            "xdelta" | "xd3" | "vcdiff" => Some(Self::XDelta),
+            "bsdiff" | "bsd" => Some(Self::BsDiff),
+            _ => None
+        }
+    }
+
+    //-----------------------------------
+    // Program function
+    //-----------------------------------
+    fn program(self) -> &'static str {
+        match self {
+            Self::XDelta => "xdelta3",
+            Self::BsDiff => "bspatch",
+        }
+    }
+
+    //-----------------------------------
+    // Apply function
+    //-----------------------------------
+    // Patch tools only read the source file
+    fn apply(self, source: &Path, patch: &Path, output: &Path) -> Result<(), String> {
+        let mut command = Command::new(self.program());
+
+        match self {
+            Self::XDelta => command.arg("-d").arg("-f").arg("-s").arg(source).arg(patch).arg(output),
+            Self::BsDiff => command.arg(source).arg(output).arg(patch),
+        };
+
+        let result = command.output()
+            .map_err(|error| {
+                if error.kind() == io::ErrorKind::NotFound {
+                    format!("{} is not installed", self.program())
+                } else {
+                    error.to_string()
+                }
+            })?;
+
+        if result.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&result.stderr);
+
+            Err(stderr.lines().last().unwrap_or("patch failed").to_owned())
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// GLOBAL: Functions
+//------------------------------------------------------------------------------
+//---------------------------------------
+// Patches folder function
+//---------------------------------------
+pub fn patches_folder() -> Option<PathBuf> {
+    xdg::BaseDirectories::new().create_data_directory(PATCHES_FOLDER).ok()
+}
+
+//---------------------------------------
+// Find patches function
+//---------------------------------------
+pub fn find_patches() -> Vec<(PathBuf, PatchFormat)> {
+    let Some(entries) = patches_folder().and_then(|folder| fs::read_dir(folder).ok()) else {
+        return vec![]
+    };
+
+    let mut patches: Vec<(PathBuf, PatchFormat)> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter_map(|path| PatchFormat::from_path(&path).map(|format| (path, format)))
+        .collect();
+
+    patches.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+    patches
+}
+
+//---------------------------------------
+// Upgrade IWAD function
+//---------------------------------------
+// Tries each patch in turn: the output is written to a temporary file in the
+// target folder, and kept only if it is identified as the canonical version of
+// the same game (no downgrades or side grades). The source file is never
+// modified. Blocks until done, call from a worker thread.
+pub fn upgrade_iwad(source: &Path, target_folder: &Path) -> Result<(&'static IWadData, PathBuf), String> {
+    let database = WadDatabase::get();
+
+    let source_name = source.display().to_string();
+
+    let hash = crc32(&source_name).map_err(|error| error.to_string())?;
+
+    let IWadMatch::Identified(source_data) = database.identify_iwad(&source_name, hash) else {
+        return Err(String::from("File is not a known IWAD version"))
+    };
+
+    if source_data.canonical {
+        return Err(format!("File is already the latest version of {}", source_data.name))
+    }
+
+    let patches = find_patches();
+
+    if patches.is_empty() {
+        return Err(format!("No xdelta or bsdiff patch files found in {}", patches_folder().unwrap_or_default().display()))
+    }
+
+    let filename = source.file_name()
+        .ok_or_else(|| String::from("Invalid file name"))?;
+
+    fs::create_dir_all(target_folder).map_err(|error| error.to_string())?;
+
+    let temp = target_folder.join(format!(".{}.{TEMP_SUFFIX}", filename.to_string_lossy()));
+    let temp_name = temp.display().to_string();
+
+    let mut errors: Vec<String> = vec![];
+
+    for (patch, format) in patches {
+        let patch_name = patch.file_name().unwrap_or_default().to_string_lossy().into_owned();
+
+        let _ = fs::remove_file(&temp);
+
+        if let Err(error) = format.apply(source, &patch, &temp) {
+            errors.push(format!("{patch_name}: {error}"));
+            continue
+        }
+
+        // Verify result against the WAD database
+        let result = crc32(&temp_name).ok()
+            .map(|hash| database.identify_iwad(&temp_name, hash));
+
+        match result {
+            Some(IWadMatch::Identified(data)) if data.id == source_data.id && data.canonical => {
+                let mut dest = target_folder.join(filename);
+
+                if dest.exists() {
+                    dest = unique_path(&dest);
+                }
+
+                fs::rename(&temp, &dest).map_err(|error| error.to_string())?;
+
+                return Ok((data, dest))
+            },
+            _ => {
+                errors.push(format!("{patch_name}: result is not the latest {} version", source_data.name));
+            }
+        }
+    }
+
+    let _ = fs::remove_file(&temp);
+
+    Err(errors.join("\n"))
+}
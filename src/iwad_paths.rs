@@ -0,0 +1,135 @@
+use std::env;
+use std::path::Path;
+
+use gtk::glib;
+use gtk::prelude::ToValue;
+
+use crate::game_stores::store_iwad_paths;
+
+//------------------------------------------------------------------------------
+// CONST VARIABLES
+//------------------------------------------------------------------------------
+pub const SYSTEM_IWAD_PATHS: [&str; 5] = [
+    "/usr/share/doom",
+    "/usr/share/games/doom",
+    "/usr/share/games/heretic",
+    "/usr/share/games/hexen",
+    "/usr/share/games/strife",
+];
+
+//------------------------------------------------------------------------------
+// ENUM: IWadPathSource
+//------------------------------------------------------------------------------
+#[derive(Default, Debug, Eq, PartialEq, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "IWadPathSource")]
+pub enum IWadPathSource {
+    #[default]
+    #[enum_value(name = "User IWAD folder")]
+    User,
+    #[enum_value(name = "DOOMWADDIR environment variable")]
+    DoomWadDir,
+    #[enum_value(name = "DOOMWADPATH environment variable")]
+    DoomWadPath,
+    #[enum_value(name = "XDG data directory")]
+    XdgData,
+    #[enum_value(name = "System folder")]
+    System,
+    #[enum_value(name = "Steam")]
+    Steam,
+    #[enum_value(name = "GOG")]
+    Gog,
+    #[enum_value(name = "Heroic Games Launcher")]
+    Heroic,
+    #[enum_value(name = "Lutris")]
+    Lutris,
+}
+
+impl IWadPathSource {
+    //-----------------------------------
+    // Display name function
+    //-----------------------------------
+    pub fn display_name(self) -> String {
+        glib::EnumValue::from_value(&self.to_value())
+            .map(|(_, value)| value.name().to_owned())
+            .unwrap_or_default()
+    }
+
+    //-----------------------------------
+    // Nick function
+    //-----------------------------------
+    pub fn nick(self) -> String {
+        glib::EnumValue::from_value(&self.to_value())
+            .map(|(_, value)| value.nick().to_owned())
+            .unwrap_or_default()
+    }
+}
+
+//------------------------------------------------------------------------------
+// STRUCT: IWadPath
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct IWadPath {
+    pub path: String,
+    pub source: IWadPathSource,
+}
+
+//------------------------------------------------------------------------------
+// GLOBAL: Functions
+//------------------------------------------------------------------------------
+//---------------------------------------
+// IWAD search paths function
+//---------------------------------------
+// Locations are returned in order of precedence:
+//   1. the user IWAD folder (from preferences)
+//   2. $DOOMWADDIR
+//   3. each entry in the colon-separated $DOOMWADPATH
+//   4. $XDG_DATA_HOME/games/doom, then $XDG_DATA_DIRS/games/doom
+//   5. the built-in system folders
+//   6. game folders found in Steam, GOG, Heroic and Lutris installations
+// Only existing folders are returned; a folder reached from several sources
+// is reported once, with the highest-precedence source.
+pub fn iwad_search_paths(user_folder: &str) -> Vec<IWadPath> {
+    let mut candidates: Vec<(String, IWadPathSource)> = vec![];
+
+    candidates.push((user_folder.to_owned(), IWadPathSource::User));
+
+    if let Ok(dir) = env::var("DOOMWADDIR") {
+        candidates.push((dir, IWadPathSource::DoomWadDir));
+    }
+
+    if let Ok(path) = env::var("DOOMWADPATH") {
+        candidates.extend(path.split(':')
+            .map(|dir| (dir.to_owned(), IWadPathSource::DoomWadPath))
+        );
+    }
+
+    let xdg_dirs = xdg::BaseDirectories::new();
+
+    candidates.extend(xdg_dirs.get_data_home().into_iter()
+        .chain(xdg_dirs.get_data_dirs())
+        .map(|dir| (dir.join("games/doom").display().to_string(), IWadPathSource::XdgData))
+    );
+
+    candidates.extend(SYSTEM_IWAD_PATHS.iter()
+        .map(|&dir| (dir.to_owned(), IWadPathSource::System))
+    );
+
+    candidates.extend(store_iwad_paths().into_iter()
+        .map(|path| (path.path, path.source))
+    );
+
+    let mut paths: Vec<IWadPath> = vec![];
+
+    for (path, source) in candidates {
+        let path = path.trim_end_matches('/').to_owned();
+
+        if path.is_empty() || !Path::new(&path).is_dir() || paths.iter().any(|p| p.path == path) {
+            continue;
+        }
+
+        paths.push(IWadPath { path, source });
+    }
+
+    paths
+}
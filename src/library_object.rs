@@ -0,0 +1,126 @@
+use std::cell::{Cell, RefCell};
+
+use gtk::{gio, glib};
+use gtk::subclass::prelude::*;
+use gtk::prelude::{ObjectExt, ToValue};
+
+use crate::utils::checksum;
+
+//------------------------------------------------------------------------------
+// ENUM: LibraryStatus
+//------------------------------------------------------------------------------
+#[derive(Default, Debug, Eq, PartialEq, Clone, Copy, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "LibraryStatus")]
+pub enum LibraryStatus {
+    #[default]
+    #[enum_value(name = "Identified IWAD")]
+    IWad,
+    #[enum_value(name = "Identified add-on")]
+    Addon,
+    #[enum_value(name = "Possibly corrupted")]
+    Corrupted,
+    #[enum_value(name = "Unknown IWAD")]
+    UnknownIWad,
+    #[enum_value(name = "PWAD")]
+    PWad,
+    #[enum_value(name = "Unknown file")]
+    Unknown,
+    #[enum_value(name = "Unreadable")]
+    Unreadable,
+}
+
+impl LibraryStatus {
+    //-----------------------------------
+    // Display name function
+    //-----------------------------------
+    pub fn display_name(self) -> String {
+        glib::EnumValue::from_value(&self.to_value())
+            .map(|(_, value)| value.name().to_owned())
+            .unwrap_or_default()
+    }
+}
+
+//------------------------------------------------------------------------------
+// MODULE: LibraryObject
+//------------------------------------------------------------------------------
+mod imp {
+    use super::*;
+
+    //-----------------------------------
+    // Private structure
+    //-----------------------------------
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::LibraryObject)]
+    pub struct LibraryObject {
+        #[property(get, set)]
+        filename: RefCell<String>,
+        #[property(get, set, builder(LibraryStatus::default()))]
+        status: Cell<LibraryStatus>,
+        #[property(get, set)]
+        description: RefCell<String>,
+        #[property(get, set)]
+        crc32: RefCell<String>,
+        #[property(get, set)]
+        md5: RefCell<String>,
+
+        pub(super) md5_pending: Cell<bool>,
+    }
+
+    //-----------------------------------
+    // Subclass
+    //-----------------------------------
+    #[glib::object_subclass]
+    impl ObjectSubclass for LibraryObject {
+        const NAME: &'static str = "LibraryObject";
+        type Type = super::LibraryObject;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for LibraryObject {}
+}
+
+//------------------------------------------------------------------------------
+// IMPLEMENTATION: LibraryObject
+//------------------------------------------------------------------------------
+glib::wrapper! {
+    pub struct LibraryObject(ObjectSubclass<imp::LibraryObject>);
+}
+
+impl LibraryObject {
+    //-----------------------------------
+    // New function
+    //-----------------------------------
+    pub fn new(filename: &str, status: LibraryStatus, description: &str, hash: Option<u32>) -> Self {
+        // Build LibraryObject
+        glib::Object::builder()
+            .property("filename", filename)
+            .property("status", status)
+            .property("description", description)
+            .property("crc32", hash.map(|hash| format!("{hash:08x}")).unwrap_or_default())
+            .build()
+    }
+
+    //-----------------------------------
+    // Public ensure MD5 function
+    //-----------------------------------
+    // MD5 digests are only computed when the library is shown (in a worker
+    // thread)
+    pub async fn ensure_md5(&self) {
+        let imp = self.imp();
+
+        if !self.md5().is_empty() || self.status() == LibraryStatus::Unreadable || imp.md5_pending.replace(true) {
+            return
+        }
+
+        let filename = self.filename();
+
+        let result = gio::spawn_blocking(move || checksum(&filename, glib::ChecksumType::Md5)).await;
+
+        imp.md5_pending.set(false);
+
+        if let Ok(Ok(md5)) = result {
+            self.set_md5(md5);
+        }
+    }
+}
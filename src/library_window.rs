@@ -0,0 +1,368 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use gtk::{gio, gdk, glib};
+use adw::subclass::prelude::*;
+use adw::prelude::*;
+use glib::clone;
+use glib::subclass::Signal;
+
+use crate::iwad_patcher::{find_patches, patches_folder, upgrade_iwad};
+use crate::library_object::{LibraryObject, LibraryStatus};
+
+//------------------------------------------------------------------------------
+// MODULE: LibraryWindow
+//------------------------------------------------------------------------------
+mod imp {
+    use super::*;
+
+    //-----------------------------------
+    // Private structure
+    //-----------------------------------
+    #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+    #[properties(wrapper_type = super::LibraryWindow)]
+    #[template(resource = "/com/github/DHXS-Launcher/ui/library_window.ui")]
+    pub struct LibraryWindow {
+        #[template_child]
+        pub(super) patches_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub(super) stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) list_box: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub(super) model: TemplateChild<gio::ListStore>,
+
+        #[property(get, set)]
+        iwad_folder: RefCell<String>,
+    }
+
+    //-----------------------------------
+    // Subclass
+    //-----------------------------------
+    #[glib::object_subclass]
+    impl ObjectSubclass for LibraryWindow {
+        const NAME: &'static str = "LibraryWindow";
+        type Type = super::LibraryWindow;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            LibraryObject::ensure_type();
+
+            klass.bind_template();
+
+            //---------------------------------------
+            // Add class key bindings
+            //---------------------------------------
+            // Close window binding
+            klass.add_binding_action(gdk::Key::Escape, gdk::ModifierType::NO_MODIFIER_MASK, "window.close");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for LibraryWindow {
+        //---------------------------------------
+        // Custom signals
+        //---------------------------------------
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("files-changed")
+                        .build(),
+                ]
+            })
+        }
+
+        //-----------------------------------
+        // Constructor
+        //-----------------------------------
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            obj.setup_widgets();
+            obj.setup_signals();
+        }
+    }
+
+    impl WidgetImpl for LibraryWindow {}
+    impl WindowImpl for LibraryWindow {}
+    impl AdwWindowImpl for LibraryWindow {}
+}
+
+//------------------------------------------------------------------------------
+// IMPLEMENTATION: LibraryWindow
+//------------------------------------------------------------------------------
+glib::wrapper! {
+    pub struct LibraryWindow(ObjectSubclass<imp::LibraryWindow>)
+        @extends adw::Window, gtk::Window, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl LibraryWindow {
+    //-----------------------------------
+    // Subtitle helper function
+    //-----------------------------------
+    fn subtitle(entry: &LibraryObject) -> String {
+        let mut subtitle = entry.status().display_name();
+
+        if !entry.description().is_empty() {
+            subtitle.push_str(&format!(" \u{2022} {}", entry.description()));
+        }
+
+        if !entry.crc32().is_empty() {
+            subtitle.push_str(&format!("\nCRC32: {}", entry.crc32()));
+        }
+
+        if !entry.md5().is_empty() {
+            subtitle.push_str(&format!("\nMD5: {}", entry.md5()));
+        }
+
+        subtitle
+    }
+
+    //-----------------------------------
+    // Create row helper function
+    //-----------------------------------
+    fn create_row(&self, entry: &LibraryObject) -> adw::ActionRow {
+        let path = Path::new(&entry.filename()).to_path_buf();
+
+        let row = adw::ActionRow::builder()
+            .title(path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default())
+            .subtitle(Self::subtitle(entry))
+            .tooltip_text(entry.filename())
+            .use_markup(false)
+            .build();
+
+        // Warning icon for corrupted and unreadable files
+        if matches!(entry.status(), LibraryStatus::Corrupted | LibraryStatus::Unreadable) {
+            let image = gtk::Image::builder()
+                .icon_name("dialog-warning-symbolic")
+                .tooltip_text(entry.status().display_name())
+                .css_classes(["warning"])
+                .build();
+
+            row.add_prefix(&image);
+        }
+
+        entry.connect_md5_notify(clone!(
+            #[weak] row,
+            move |entry| {
+                row.set_subtitle(&Self::subtitle(entry));
+            }
+        ));
+
+        // Reveal file button
+        let reveal_button = gtk::Button::builder()
+            .icon_name("folder-open-symbolic")
+            .tooltip_text("Show in Folder")
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+
+        reveal_button.connect_clicked(clone!(
+            #[weak(rename_to = window)] self,
+            move |_| {
+                gtk::FileLauncher::new(Some(&gio::File::for_path(&path)))
+                    .open_containing_folder(Some(&window), None::<&gio::Cancellable>, |_| {});
+            }
+        ));
+
+        row.add_suffix(&reveal_button);
+
+        // Copy checksum button
+        let copy_button = gtk::Button::builder()
+            .icon_name("edit-copy-symbolic")
+            .tooltip_text("Copy Checksum")
+            .valign(gtk::Align::Center)
+            .sensitive(!entry.crc32().is_empty())
+            .css_classes(["flat"])
+            .build();
+
+        copy_button.connect_clicked(clone!(
+            #[weak(rename_to = window)] self,
+            #[weak] entry,
+            move |_| {
+                // Copy MD5 if computed, CRC-32 otherwise
+                let (label, checksum) = if entry.md5().is_empty() {
+                    ("CRC32", entry.crc32())
+                } else {
+                    ("MD5", entry.md5())
+                };
+
+                window.clipboard().set_text(&checksum);
+
+                window.imp().toast_overlay.add_toast(adw::Toast::new(&format!("{label} checksum copied")));
+            }
+        ));
+
+        row.add_suffix(&copy_button);
+
+        // Apply patch button (identified IWADs only)
+        if entry.status() == LibraryStatus::IWad {
+            let patch_button = gtk::Button::builder()
+                .icon_name("software-update-available-symbolic")
+                .tooltip_text("Create Latest Version with Patch Files")
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+
+            patch_button.connect_clicked(clone!(
+                #[weak(rename_to = window)] self,
+                #[weak] entry,
+                move |button| {
+                    window.apply_patches(&entry, button);
+                }
+            ));
+
+            row.add_suffix(&patch_button);
+        }
+
+        row
+    }
+
+    //-----------------------------------
+    // Apply patches helper function
+    //-----------------------------------
+    fn apply_patches(&self, entry: &LibraryObject, button: &gtk::Button) {
+        if find_patches().is_empty() {
+            let dialog = adw::AlertDialog::builder()
+                .heading("No Patch Files")
+                .body(format!(
+                    "Place xdelta or bsdiff patch files in {} to update your IWAD files to their latest version. Patched files are verified and saved in the IWAD folder; original files are not modified.",
+                    patches_folder().unwrap_or_default().display()
+                ))
+                .build();
+
+            dialog.add_responses(&[("ok", "_Ok")]);
+
+            dialog.present(Some(self));
+            return
+        }
+
+        // Patch in a worker thread (button disabled until done)
+        let source = PathBuf::from(entry.filename());
+        let target_folder = PathBuf::from(self.iwad_folder());
+
+        button.set_sensitive(false);
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = window)] self,
+            #[weak] button,
+            async move {
+                let result = gio::spawn_blocking(move || upgrade_iwad(&source, &target_folder))
+                    .await
+                    .unwrap_or_else(|_| Err(String::from("Patching failed")));
+
+                button.set_sensitive(true);
+
+                match result {
+                    Ok((data, dest)) => {
+                        let filename = dest.file_name().unwrap_or_default().to_string_lossy();
+
+                        window.imp().toast_overlay.add_toast(adw::Toast::new(&format!("{} {} saved as {filename}", data.name, data.version)));
+
+                        window.emit_by_name::<()>("files-changed", &[]);
+                    },
+                    Err(error) => {
+                        let dialog = adw::AlertDialog::builder()
+                            .heading("Could Not Patch File")
+                            .body(error)
+                            .build();
+
+                        dialog.add_responses(&[("ok", "_Ok")]);
+
+                        dialog.present(Some(&window));
+                    }
+                }
+            }
+        ));
+    }
+
+    //-----------------------------------
+    // Compute MD5 digests helper function
+    //-----------------------------------
+    // Files are hashed one at a time, rows are updated as digests are computed
+    fn compute_md5_digests(&self) {
+        let entries: Vec<LibraryObject> = self.imp().model.iter::<LibraryObject>().flatten().collect();
+
+        glib::spawn_future_local(async move {
+            for entry in entries {
+                entry.ensure_md5().await;
+            }
+        });
+    }
+
+    //-----------------------------------
+    // Setup widgets
+    //-----------------------------------
+    fn setup_widgets(&self) {
+        let imp = self.imp();
+
+        imp.list_box.bind_model(Some(&imp.model.get()), clone!(
+            #[weak(rename_to = window)] self,
+            #[upgrade_or_panic]
+            move |obj| {
+                let entry = obj.downcast_ref::<LibraryObject>()
+                    .expect("Must be a 'LibraryObject'");
+
+                window.create_row(entry).upcast()
+            }
+        ));
+    }
+
+    //-----------------------------------
+    // Setup signals
+    //-----------------------------------
+    fn setup_signals(&self) {
+        let imp = self.imp();
+
+        // Patches folder button clicked signal
+        imp.patches_button.connect_clicked(clone!(
+            #[weak(rename_to = window)] self,
+            move |_| {
+                if let Some(folder) = patches_folder() {
+                    gtk::FileLauncher::new(Some(&gio::File::for_path(folder)))
+                        .launch(Some(&window), None::<&gio::Cancellable>, |_| {});
+                }
+            }
+        ));
+
+        // Window show signal (compute MD5 digests when library is shown)
+        self.connect_show(|window| {
+            window.compute_md5_digests();
+        });
+    }
+
+    //-----------------------------------
+    // Public set entries function
+    //-----------------------------------
+    pub fn set_entries(&self, entries: &[LibraryObject]) {
+        let imp = self.imp();
+
+        imp.model.splice(0, imp.model.n_items(), entries);
+
+        imp.stack.set_visible_child_name(if entries.is_empty() { "empty" } else { "list" });
+
+        if self.is_visible() {
+            self.compute_md5_digests();
+        }
+    }
+}
+
+impl Default for LibraryWindow {
+    //-----------------------------------
+    // Default constructor
+    //-----------------------------------
+    fn default() -> Self {
+        glib::Object::builder().build()
+    }
+}
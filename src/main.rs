@@ -6,15 +6,32 @@ mod engine_settings;
 mod iwad_combo_row;
 mod iwad_object;
 mod pwad_select_row;
+mod pwad_files_dialog;
+mod pwad_presets_dialog;
 mod cheats_window;
 mod cheat_object;
+mod library_window;
+mod library_object;
+mod pwad_library_window;
+mod pwad_object;
+mod import_dialog;
 mod preferences_dialog;
 mod folder_select_row;
 mod utils;
 mod iwad_data;
+mod iwad_paths;
+mod game_stores;
 mod pwad_data;
+mod pwad_info;
+mod pwad_cache;
+mod pwad_bundle;
+mod pwad_preset;
+mod wad_database;
+mod wad_reader;
+mod iwad_patcher;
 mod engine_data;
 mod graphics_data;
+mod companion_data;
 
 use gtk::{gio, glib};
 use gtk::prelude::*;
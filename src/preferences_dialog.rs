@@ -1,11 +1,17 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::sync::OnceLock;
 
 use gtk::{gio, glib};
 use adw::subclass::prelude::*;
 use adw::prelude::*;
 use glib::clone;
+use glib::subclass::Signal;
 
 use crate::folder_select_row::FolderSelectRow;
+use crate::game_stores::refresh_store_iwad_paths;
+use crate::iwad_paths::{iwad_search_paths, IWadPathSource};
+use crate::pwad_cache::{cache_size, clear_cache, MAX_CACHE_SIZE};
+use crate::utils::env_expand;
 
 //------------------------------------------------------------------------------
 // MODULE: PreferencesDialog
@@ -25,13 +31,40 @@ mod imp {
         #[template_child]
         pub(super) pwad_row: TemplateChild<FolderSelectRow>,
 
+        #[template_child]
+        pub(super) search_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub(super) rescan_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) location_row: TemplateChild<adw::ComboRow>,
+
+        #[template_child]
+        pub(super) remember_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub(super) hide_engines_row: TemplateChild<adw::SwitchRow>,
+
+        #[template_child]
+        pub(super) cache_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub(super) clear_cache_button: TemplateChild<gtk::Button>,
+
         #[template_child]
         pub(super) reset_button: TemplateChild<adw::ButtonRow>,
 
+        pub(super) search_rows: RefCell<Vec<adw::ActionRow>>,
+
         #[property(get, set)]
         iwad_folder: RefCell<String>,
         #[property(get, set)]
         pwad_folder: RefCell<String>,
+        #[property(get, set)]
+        disabled_iwad_paths: RefCell<Vec<String>>,
+        #[property(get, set)]
+        preferred_iwad_source: RefCell<String>,
+        #[property(get, set)]
+        remember_game_selections: Cell<bool>,
+        #[property(get, set)]
+        hide_incompatible_engines: Cell<bool>,
 
         #[property(get, set)]
         iwad_default_folder: RefCell<String>,
@@ -59,6 +92,19 @@ mod imp {
 
     #[glib::derived_properties]
     impl ObjectImpl for PreferencesDialog {
+        //-----------------------------------
+        // Custom signals
+        //-----------------------------------
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("search-paths-refreshed")
+                        .build(),
+                ]
+            })
+        }
+
         //-----------------------------------
         // Constructor
         //-----------------------------------
@@ -113,6 +159,124 @@ impl PreferencesDialog {
             .sync_create()
             .bidirectional()
             .build();
+
+        self.bind_property("remember-game-selections", &imp.remember_row.get(), "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+
+        self.bind_property("hide-incompatible-engines", &imp.hide_engines_row.get(), "active")
+            .sync_create()
+            .bidirectional()
+            .build();
+
+        // Populate preferred location combo row (first entry is search order)
+        let location_names = std::iter::once(String::from("First Search Location"))
+            .chain(Self::location_sources().iter().map(|source| source.display_name()))
+            .collect::<Vec<String>>();
+
+        imp.location_row.set_model(Some(&gtk::StringList::new(&location_names.iter().map(String::as_str).collect::<Vec<&str>>())));
+
+        self.bind_property("preferred-iwad-source", &imp.location_row.get(), "selected")
+            .transform_to(|_, nick: String| {
+                let index = Self::location_sources().iter()
+                    .position(|source| source.nick() == nick)
+                    .map_or(0, |index| index + 1);
+
+                Some(index as u32)
+            })
+            .transform_from(|_, index: u32| {
+                let nick = (index as usize).checked_sub(1)
+                    .and_then(|index| Self::location_sources().get(index).map(|source| source.nick()))
+                    .unwrap_or_default();
+
+                Some(nick)
+            })
+            .sync_create()
+            .bidirectional()
+            .build();
+    }
+
+    //---------------------------------------
+    // Location sources helper function
+    //---------------------------------------
+    fn location_sources() -> Vec<IWadPathSource> {
+        let enum_class = glib::EnumClass::new::<IWadPathSource>();
+
+        enum_class.values().iter()
+            .filter_map(|value| value.to_value(&enum_class).get::<IWadPathSource>().ok())
+            .collect()
+    }
+
+    //---------------------------------------
+    // Update cache row helper function
+    //---------------------------------------
+    fn update_cache_row(&self) {
+        let imp = self.imp();
+
+        let size = cache_size();
+
+        imp.cache_row.set_subtitle(&format!("{} used, least recently used files are removed above {}", glib::format_size(size), glib::format_size(MAX_CACHE_SIZE)));
+
+        imp.clear_cache_button.set_sensitive(size > 0);
+    }
+
+    //---------------------------------------
+    // Populate search paths helper function
+    //---------------------------------------
+    fn populate_search_paths(&self) {
+        let imp = self.imp();
+
+        for row in imp.search_rows.take() {
+            imp.search_group.remove(&row);
+        }
+
+        let disabled_paths = self.disabled_iwad_paths();
+
+        let rows = iwad_search_paths(&env_expand(&self.iwad_folder())).into_iter()
+            .map(|path| {
+                // User folder is always searched
+                if path.source == IWadPathSource::User {
+                    return adw::ActionRow::builder()
+                        .title(path.path.as_str())
+                        .subtitle(path.source.display_name())
+                        .use_markup(false)
+                        .build()
+                }
+
+                let row = adw::SwitchRow::builder()
+                    .title(path.path.as_str())
+                    .subtitle(path.source.display_name())
+                    .use_markup(false)
+                    .active(!disabled_paths.contains(&path.path))
+                    .build();
+
+                row.connect_active_notify(clone!(
+                    #[weak(rename_to = dialog)] self,
+                    move |row| {
+                        let path = row.title().to_string();
+
+                        let mut disabled_paths = dialog.disabled_iwad_paths();
+
+                        disabled_paths.retain(|disabled| *disabled != path);
+
+                        if !row.is_active() {
+                            disabled_paths.push(path);
+                        }
+
+                        dialog.set_disabled_iwad_paths(disabled_paths);
+                    }
+                ));
+
+                row.upcast()
+            })
+            .collect::<Vec<adw::ActionRow>>();
+
+        for row in &rows {
+            imp.search_group.add(row);
+        }
+
+        imp.search_rows.replace(rows);
     }
 
     //-----------------------------------
@@ -121,6 +285,54 @@ impl PreferencesDialog {
     fn setup_signals(&self) {
         let imp = self.imp();
 
+        // IWAD folder property notify signal
+        self.connect_iwad_folder_notify(|dialog| {
+            dialog.populate_search_paths();
+        });
+
+        // Disabled IWAD paths property notify signal
+        self.connect_disabled_iwad_paths_notify(clone!(
+            #[weak] imp,
+            move |dialog| {
+                let disabled_paths = dialog.disabled_iwad_paths();
+
+                for row in imp.search_rows.borrow().iter() {
+                    if let Some(row) = row.downcast_ref::<adw::SwitchRow>() {
+                        row.set_active(!disabled_paths.contains(&row.title().to_string()));
+                    }
+                }
+            }
+        ));
+
+        // Rescan game stores button clicked signal
+        imp.rescan_button.connect_clicked(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |_| {
+                refresh_store_iwad_paths();
+
+                dialog.populate_search_paths();
+
+                dialog.emit_by_name::<()>("search-paths-refreshed", &[]);
+            }
+        ));
+
+        // Dialog map signal (extracted files change when launching games)
+        self.connect_map(|dialog| {
+            dialog.update_cache_row();
+        });
+
+        // Clear cache button clicked signal
+        imp.clear_cache_button.connect_clicked(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |_| {
+                if let Err(error) = clear_cache() {
+                    dialog.add_toast(adw::Toast::new(&format!("Could not clear extracted files: {error}")));
+                }
+
+                dialog.update_cache_row();
+            }
+        ));
+
         // Preferences reset button clicked signal
         imp.reset_button.connect_activated(clone!(
             #[weak(rename_to = window)] self,
@@ -128,7 +340,7 @@ impl PreferencesDialog {
             move |_| {
                 let reset_dialog = adw::AlertDialog::builder()
                     .heading("Reset Paths?")
-                    .body("Reset all paths to their default values.")
+                    .body("Reset all paths to their default values, re-enable all search locations and reset the preferred location.")
                     .default_response("reset")
                     .build();
 
@@ -139,11 +351,15 @@ impl PreferencesDialog {
                     &window,
                     None::<&gio::Cancellable>,
                     clone!(
+                        #[weak] window,
                         #[weak] imp,
                         move |response| {
                             if response == "reset" {
                                 imp.iwad_row.reset_to_default();
                                 imp.pwad_row.reset_to_default();
+
+                                window.set_disabled_iwad_paths(Vec::<String>::new());
+                                window.set_preferred_iwad_source("");
                             }
                         }
                     )
@@ -0,0 +1,260 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use gtk::glib;
+
+use crate::iwad_data::IWadID;
+
+//------------------------------------------------------------------------------
+// CONST VARIABLES
+//------------------------------------------------------------------------------
+// Optional manifest in the bundle folder:
+//
+//   [Bundle]
+//   Files=gameplay.pk3;hud.wad;patch.deh   (load order, relative to folder)
+//   Game=DOOM2;PLUTONIA;TNT                (IWadID keys, optional)
+//   Engine=GZDoom                          (recommended engine, optional)
+//   Switches=-fast                         (extra switches, optional)
+const MANIFEST_FILE: &str = "dhxs-bundle.ini";
+
+const MANIFEST_GROUP: &str = "Bundle";
+
+// File types loaded from bundles without manifest (sorted by name)
+const BUNDLE_EXTENSIONS: [&str; 7] = ["wad", "pk3", "pk7", "zip", "kpf", "deh", "bex"];
+
+//------------------------------------------------------------------------------
+// STRUCT: PWadBundle
+//------------------------------------------------------------------------------
+// Folder of PWAD files loaded together, in order
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PWadBundle {
+    pub files: Vec<PathBuf>,
+    pub games: IWadID,
+    pub engine: Option<String>,
+    pub switches: Option<String>,
+    pub has_manifest: bool,
+}
+
+impl PWadBundle {
+    //-----------------------------------
+    // Is bundle function
+    //-----------------------------------
+    pub fn is_bundle(path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    //-----------------------------------
+    // Open function
+    //-----------------------------------
+    pub fn open(folder: &Path) -> Result<Self, String> {
+        let manifest = folder.join(MANIFEST_FILE);
+
+        if manifest.is_file() {
+            let data = fs::read_to_string(&manifest)
+                .map_err(|error| format!("{MANIFEST_FILE}: {error}"))?;
+
+            Self::from_manifest(folder, &data)
+        } else {
+            Self::from_folder(folder)
+        }
+    }
+
+    //-----------------------------------
+    // From folder function
+    //-----------------------------------
+    fn from_folder(folder: &Path) -> Result<Self, String> {
+        let mut files: Vec<PathBuf> = fs::read_dir(folder)
+            .map_err(|error| error.to_string())?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .filter(|path| !path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| BUNDLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            })
+            .collect();
+
+        if files.is_empty() {
+            return Err(String::from("no PWAD files found in folder"))
+        }
+
+        files.sort_by_key(|path| path.file_name().map(|name| name.to_string_lossy().to_lowercase()));
+
+        Ok(Self {
+            files,
+            ..Default::default()
+        })
+    }
+
+    //-----------------------------------
+    // From manifest function
+    //-----------------------------------
+    fn from_manifest(folder: &Path, data: &str) -> Result<Self, String> {
+        let keyfile = glib::KeyFile::new();
+
+        keyfile.load_from_data(data, glib::KeyFileFlags::NONE)
+            .map_err(|error| format!("{MANIFEST_FILE}: {error}"))?;
+
+        // Files (relative paths inside folder, in load order)
+        let names = keyfile.string_list(MANIFEST_GROUP, "Files")
+            .map_err(|_| format!("{MANIFEST_FILE}: missing Files key"))?;
+
+        let files = names.iter()
+            .map(|name| {
+                let relative = Path::new(name.as_str());
+
+                if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+                    return Err(format!("{MANIFEST_FILE}: invalid file '{name}'"))
+                }
+
+                let file = folder.join(relative);
+
+                if !file.is_file() {
+                    return Err(format!("{MANIFEST_FILE}: file '{name}' not found"))
+                }
+
+                Ok(file)
+            })
+            .collect::<Result<Vec<PathBuf>, String>>()?;
+
+        if files.is_empty() {
+            return Err(format!("{MANIFEST_FILE}: no files listed"))
+        }
+
+        // Required games
+        let games = keyfile.string_list(MANIFEST_GROUP, "Game").unwrap_or_default().iter()
+            .try_fold(IWadID::empty(), |games, game| {
+                IWadID::from_key(game.as_str())
+                    .map(|id| games | id)
+                    .ok_or_else(|| format!("{MANIFEST_FILE}: unknown game '{game}'"))
+            })?;
+
+        // Recommended engine and extra switches
+        let optional_key = |key: &str| {
+            keyfile.string(MANIFEST_GROUP, key).ok()
+                .map(|value| value.trim().to_owned())
+                .filter(|value| !value.is_empty())
+        };
+
+        Ok(Self {
+            files,
+            games,
+            engine: optional_key("Engine"),
+            switches: optional_key("Switches"),
+            has_manifest: true,
+        })
+    }
+
+    //-----------------------------------
+    // Description function
+    //-----------------------------------
+    // Files in load order followed by manifest settings
+    pub fn description(&self) -> String {
+        let mut lines: Vec<String> = vec![if self.has_manifest {
+            format!("Mod bundle, loaded as listed in {MANIFEST_FILE}")
+        } else {
+            String::from("Mod bundle, loaded in file name order")
+        }];
+
+        lines.extend(self.files.iter()
+            .enumerate()
+            .map(|(i, file)| format!("{}. {}", i + 1, file.file_name().map(|name| name.to_string_lossy()).unwrap_or_default()))
+        );
+
+        let games = self.games.display_names();
+
+        if !games.is_empty() {
+            lines.push(format!("Requires {}", games.join(", ")));
+        }
+
+        if let Some(engine) = &self.engine {
+            lines.push(format!("Recommended source port: {engine}"));
+        }
+
+        if let Some(switches) = &self.switches {
+            lines.push(format!("Additional switches: {switches}"));
+        }
+
+        lines.join("\n")
+    }
+}
+
+//------------------------------------------------------------------------------
+// TESTS
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //---------------------------------------
+    // Test folder helper function
+    //---------------------------------------
+    fn temp_folder(name: &str, files: &[&str]) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("dhxs-launcher-test-{}-{name}", std::process::id()));
+
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+
+        for file in files {
+            fs::write(folder.join(file), b"PWAD").unwrap();
+        }
+
+        folder
+    }
+
+    #[test]
+    fn folder_files_are_sorted_by_name() {
+        let folder = temp_folder("bundle-sorted", &["b.wad", "A.pk3", "c.deh", "readme.txt", ".hidden.wad"]);
+
+        let bundle = PWadBundle::open(&folder).unwrap();
+
+        assert_eq!(bundle.files, vec![folder.join("A.pk3"), folder.join("b.wad"), folder.join("c.deh")]);
+        assert!(!bundle.has_manifest);
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn empty_folder_is_rejected() {
+        let folder = temp_folder("bundle-empty", &["readme.txt"]);
+
+        assert!(PWadBundle::open(&folder).is_err());
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn manifest_sets_order_and_options() {
+        let folder = temp_folder("bundle-manifest", &["a.wad", "b.pk3", "patch.deh"]);
+
+        let manifest = "[Bundle]\nFiles=patch.deh;b.pk3;a.wad\nGame=DOOM2;TNT\nEngine=GZDoom\nSwitches=-fast\n";
+
+        let bundle = PWadBundle::from_manifest(&folder, manifest).unwrap();
+
+        assert_eq!(bundle.files, vec![folder.join("patch.deh"), folder.join("b.pk3"), folder.join("a.wad")]);
+        assert_eq!(bundle.games, IWadID::DOOM2 | IWadID::TNT);
+        assert_eq!(bundle.engine.as_deref(), Some("GZDoom"));
+        assert_eq!(bundle.switches.as_deref(), Some("-fast"));
+        assert!(bundle.description().contains("Requires Doom II, Final Doom"));
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn invalid_manifests_are_rejected() {
+        let folder = temp_folder("bundle-invalid", &["a.wad"]);
+
+        for manifest in [
+            "[Bundle]\nGame=DOOM2\n",
+            "[Bundle]\nFiles=missing.wad\n",
+            "[Bundle]\nFiles=../a.wad\n",
+            "[Bundle]\nFiles=a.wad\nGame=QUAKE\n",
+        ] {
+            assert!(PWadBundle::from_manifest(&folder, manifest).is_err(), "{manifest}");
+        }
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::wad_reader::{ZipArchive, ZipEntry};
+
+//------------------------------------------------------------------------------
+// CONST VARIABLES
+//------------------------------------------------------------------------------
+// Cache folder for files extracted from zipped PWADs (one folder per file,
+// named after its CRC-32 and size)
+const CACHE_FOLDER: &str = "dhxs-launcher/extracted";
+
+// Least recently used files are removed above this size
+pub const MAX_CACHE_SIZE: u64 = 1024 * 1024 * 1024;
+
+// File types extracted from archives
+const EXTRACTED_EXTENSIONS: [&str; 3] = ["wad", "deh", "bex"];
+
+const TEMP_SUFFIX: &str = "part";
+
+//------------------------------------------------------------------------------
+// GLOBAL: Helper functions
+//------------------------------------------------------------------------------
+//---------------------------------------
+// Is extracted helper function
+//---------------------------------------
+fn is_extracted(entry: &ZipEntry) -> bool {
+    !entry.is_dir() && Path::new(&entry.name).extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXTRACTED_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+//---------------------------------------
+// Cache entries helper function
+//---------------------------------------
+// Extracted files with their size and last use time
+fn cache_entries(cache: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    let Ok(folders) = fs::read_dir(cache) else { return vec![] };
+
+    folders.flatten()
+        .filter_map(|folder| fs::read_dir(folder.path()).ok())
+        .flat_map(|files| files.flatten())
+        .filter_map(|file| {
+            let metadata = file.metadata().ok()?;
+
+            Some((file.path(), metadata.len(), metadata.modified().ok()?))
+        })
+        .collect()
+}
+
+//---------------------------------------
+// Prune cache helper function
+//---------------------------------------
+// Removes least recently used files (except the given ones) until the cache
+// fits its maximum size
+fn prune_cache(cache: &Path, keep: &[PathBuf]) {
+    let mut entries = cache_entries(cache);
+
+    let mut size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+
+    entries.sort_unstable_by_key(|(_, _, modified)| *modified);
+
+    for (path, file_size, _) in entries {
+        if size <= MAX_CACHE_SIZE {
+            break
+        }
+
+        if keep.contains(&path) {
+            continue
+        }
+
+        if fs::remove_file(&path).is_ok() {
+            size -= file_size;
+
+            if let Some(folder) = path.parent() {
+                let _ = fs::remove_dir(folder);
+            }
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// GLOBAL: Extract PWADs function
+//------------------------------------------------------------------------------
+// Extracts the WAD and DeHackEd files of an archive to the cache (files already
+// cached are reused) and returns them in archive order
+pub fn extract_pwads(archive_path: &Path) -> io::Result<Vec<PathBuf>> {
+    let cache = xdg::BaseDirectories::new().create_cache_directory(CACHE_FOLDER)?;
+
+    let mut archive = ZipArchive::open(archive_path)?;
+
+    let entries: Vec<ZipEntry> = archive.entries.iter()
+        .filter(|entry| is_extracted(entry))
+        .cloned()
+        .collect();
+
+    if entries.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no WAD or DeHackEd files found in archive"))
+    }
+
+    let mut files: Vec<PathBuf> = vec![];
+
+    for entry in &entries {
+        let name = Path::new(&entry.name).file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid file name in archive"))?;
+
+        let folder = cache.join(format!("{:08x}-{}", entry.crc32, entry.size));
+        let file = folder.join(name);
+
+        if fs::metadata(&file).is_ok_and(|metadata| metadata.len() == u64::from(entry.size)) {
+            // Mark cached file as recently used
+            fs::File::options().append(true).open(&file)?.set_modified(SystemTime::now())?;
+        } else {
+            fs::create_dir_all(&folder)?;
+
+            let temp_file = file.with_extension(TEMP_SUFFIX);
+
+            archive.extract(entry, &temp_file)?;
+
+            fs::rename(&temp_file, &file)?;
+        }
+
+        files.push(file);
+    }
+
+    prune_cache(&cache, &files);
+
+    Ok(files)
+}
+
+//------------------------------------------------------------------------------
+// GLOBAL: Cache size function
+//------------------------------------------------------------------------------
+pub fn cache_size() -> u64 {
+    xdg::BaseDirectories::new().find_cache_file(CACHE_FOLDER)
+        .map_or(0, |cache| cache_entries(&cache).iter().map(|(_, size, _)| size).sum())
+}
+
+//------------------------------------------------------------------------------
+// GLOBAL: Clear cache function
+//------------------------------------------------------------------------------
+// Files in use by a running engine stay readable until it exits
+pub fn clear_cache() -> io::Result<()> {
+    match xdg::BaseDirectories::new().find_cache_file(CACHE_FOLDER) {
+        Some(cache) => fs::remove_dir_all(cache),
+        None => Ok(())
+    }
+}
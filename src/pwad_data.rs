@@ -1,31 +1,73 @@
+use crate::engine_data::EngineFeatures;
 use crate::iwad_data::IWadID;
 
+//------------------------------------------------------------------------------
+// CONST VARIABLES
+//------------------------------------------------------------------------------
+// File extensions of engine resource archives (scanned in addition to IWAD_EXTENSIONS)
+pub const PWAD_RESOURCE_EXTENSIONS: [&str; 1] = ["kpf"];
+
+//------------------------------------------------------------------------------
+// ENUM: PWadType
+//------------------------------------------------------------------------------
+#[derive(Default, Debug, Eq, PartialEq, Clone, Copy)]
+pub enum PWadType {
+    // Loaded when selected by the user
+    #[default]
+    Addon,
+    // Loaded when the engine supports the required features
+    Resource,
+}
+
+impl PWadType {
+    //-----------------------------------
+    // From key function
+    //-----------------------------------
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "Addon" => Some(Self::Addon),
+            "Resource" => Some(Self::Resource),
+            _ => None
+        }
+    }
+}
+
 //------------------------------------------------------------------------------
 // STRUCT: PWadData
 //------------------------------------------------------------------------------
-#[allow(dead_code)]
 #[derive(Debug)]
-pub struct PWadData<'a> {
+pub struct PWadData {
+    pub group: String,
     pub id: IWadID,
-    pub name: &'a str,
-    pub description: &'a str
+    pub name: String,
+    pub description: String,
+    pub crc32: Option<u32>,
+    pub file: Option<glob::Pattern>,
+    pub kind: PWadType,
+    pub requires: EngineFeatures,
+    pub warp: Option<String>,
+    pub expansion: bool,
 }
 
-//------------------------------------------------------------------------------
-// PWAD DATA
-//------------------------------------------------------------------------------
-pub const PWAD_HASHMAP: [(u32, PWadData); 3] = [
-    // SIGIL I+II --------------------------------------------------------------
-    (
-        0xf9216574,
-        PWadData { id: IWadID::UDOOM, name: "SIGIL", description: "SIGIL main PWAD" }
-    ),
-    (
-        0xb7679050,
-        PWadData { id: IWadID::UDOOM, name: "SIGIL", description: "SIGIL compatibility PWAD" }
-    ),
-    (
-        0xd210db36,
-        PWadData { id: IWadID::UDOOM, name: "SIGIL II", description: "SIGIL II main PWAD" }
-    ),
-];
+impl PWadData {
+    //-----------------------------------
+    // Is expansion function
+    //-----------------------------------
+    // Expansions are listed as separate games (with their default warp)
+    pub fn is_expansion(&self) -> bool {
+        self.kind == PWadType::Addon && self.expansion
+    }
+
+    //-----------------------------------
+    // Matches file name function
+    //-----------------------------------
+    pub fn matches_filename(&self, filename: &str) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+
+        self.file.as_ref()
+            .is_some_and(|pattern| pattern.matches_with(filename, options))
+    }
+}
@@ -0,0 +1,489 @@
+use std::cell::RefCell;
+use std::path::Path;
+
+use gtk::{gio, gdk, glib};
+use adw::subclass::prelude::*;
+use adw::prelude::*;
+use glib::clone;
+
+use crate::engine_data::{EngineFeatures, FileKind, ENGINE_ARRAY};
+use crate::pwad_bundle::PWadBundle;
+use crate::utils::{append_files, file_to_path, path_to_file};
+use crate::wad_reader::WadFile;
+
+//------------------------------------------------------------------------------
+// MODULE: PWadFilesDialog
+//------------------------------------------------------------------------------
+mod imp {
+    use super::*;
+
+    //-----------------------------------
+    // Private structure
+    //-----------------------------------
+    #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+    #[properties(wrapper_type = super::PWadFilesDialog)]
+    #[template(resource = "/com/github/DHXS-Launcher/ui/pwad_files_dialog.ui")]
+    pub struct PWadFilesDialog {
+        #[template_child]
+        pub(super) add_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) add_folder_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) clear_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) list_box: TemplateChild<gtk::ListBox>,
+
+        pub(super) rows: RefCell<Vec<adw::ActionRow>>,
+
+        #[property(get, set)]
+        initial_folder: RefCell<String>,
+        #[property(get, set)]
+        files: RefCell<Vec<String>>,
+    }
+
+    //-----------------------------------
+    // Subclass
+    //-----------------------------------
+    #[glib::object_subclass]
+    impl ObjectSubclass for PWadFilesDialog {
+        const NAME: &'static str = "PWadFilesDialog";
+        type Type = super::PWadFilesDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PWadFilesDialog {
+        //-----------------------------------
+        // Constructor
+        //-----------------------------------
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            obj.setup_widgets();
+            obj.setup_signals();
+        }
+    }
+
+    impl WidgetImpl for PWadFilesDialog {}
+    impl AdwDialogImpl for PWadFilesDialog {}
+}
+
+//------------------------------------------------------------------------------
+// IMPLEMENTATION: PWadFilesDialog
+//------------------------------------------------------------------------------
+glib::wrapper! {
+    pub struct PWadFilesDialog(ObjectSubclass<imp::PWadFilesDialog>)
+        @extends adw::Dialog, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::ShortcutManager;
+}
+
+impl PWadFilesDialog {
+    //-----------------------------------
+    // New function
+    //-----------------------------------
+    pub fn new(files: Vec<String>, initial_folder: &str) -> Self {
+        glib::Object::builder()
+            .property("files", files)
+            .property("initial-folder", initial_folder)
+            .build()
+    }
+
+    //-----------------------------------
+    // Setup widgets
+    //-----------------------------------
+    fn setup_widgets(&self) {
+        let imp = self.imp();
+
+        // Accept files dropped from a file manager
+        let drop_target = gtk::DropTarget::new(gdk::FileList::static_type(), gdk::DragAction::COPY);
+
+        drop_target.connect_drop(clone!(
+            #[weak(rename_to = dialog)] self,
+            #[upgrade_or]
+            false,
+            move |_, value, _, _| {
+                let Ok(file_list) = value.get::<gdk::FileList>() else {
+                    return false
+                };
+
+                dialog.append_files(file_list.files().iter().map(file_to_path).collect());
+
+                true
+            }
+        ));
+
+        imp.stack.add_controller(drop_target);
+
+        self.update_rows(None);
+    }
+
+    //-----------------------------------
+    // Setup signals
+    //-----------------------------------
+    fn setup_signals(&self) {
+        let imp = self.imp();
+
+        // Add button clicked signal
+        imp.add_button.connect_clicked(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |_| {
+                dialog.show_file_chooser();
+            }
+        ));
+
+        // Add folder button clicked signal
+        imp.add_folder_button.connect_clicked(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |_| {
+                dialog.show_folder_chooser();
+            }
+        ));
+
+        // Clear button clicked signal
+        imp.clear_button.connect_clicked(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |_| {
+                dialog.set_files(vec![]);
+                dialog.update_rows(None);
+            }
+        ));
+    }
+
+    //-----------------------------------
+    // Show file chooser helper function
+    //-----------------------------------
+    fn show_file_chooser(&self) {
+        // Create dialog
+        let dialog = gtk::FileDialog::builder()
+            .title("PWAD Files")
+            .modal(true)
+            .accept_label("Add")
+            .build();
+
+        // Set filters for dialog
+        let all_filter = gtk::FileFilter::new();
+        all_filter.set_name(Some("All Files"));
+        all_filter.add_pattern("*");
+
+        let pwad_filter = gtk::FileFilter::new();
+        pwad_filter.set_name(Some("WAD/PK3/PK7 files and DeHackEd patches"));
+        pwad_filter.add_mime_type("application/x-doom-wad");
+        pwad_filter.add_mime_type("application/zip");
+        pwad_filter.add_mime_type("application/x-7z-compressed");
+        pwad_filter.add_suffix("deh");
+        pwad_filter.add_suffix("bex");
+
+        let dehacked_filter = gtk::FileFilter::new();
+        dehacked_filter.set_name(Some("DeHackEd patches"));
+        dehacked_filter.add_suffix("deh");
+        dehacked_filter.add_suffix("bex");
+
+        dialog.set_default_filter(Some(&pwad_filter));
+        dialog.set_filters(Some(&gio::ListStore::from_iter([pwad_filter, dehacked_filter, all_filter])));
+
+        // Set initial location for dialog (folder of last file)
+        let files = self.files();
+
+        match files.last().and_then(|file| Path::new(file).parent()) {
+            Some(folder) => dialog.set_initial_folder(path_to_file(&folder.display().to_string()).as_ref()),
+            None => dialog.set_initial_folder(path_to_file(&self.initial_folder()).as_ref()),
+        }
+
+        // Get root window
+        let root = self.root()
+            .and_downcast::<gtk::Window>();
+
+        // Show dialog
+        dialog.open_multiple(root.as_ref(), None::<&gio::Cancellable>, clone!(
+            #[weak(rename_to = files_dialog)] self,
+            move |result| {
+                if let Ok(file_list) = result {
+                    files_dialog.append_files(file_list.iter::<gio::File>()
+                        .flatten()
+                        .map(|file| file_to_path(&file))
+                        .collect()
+                    );
+                }
+            }
+        ));
+    }
+
+    //-----------------------------------
+    // Show folder chooser helper function
+    //-----------------------------------
+    fn show_folder_chooser(&self) {
+        // Create dialog
+        let dialog = gtk::FileDialog::builder()
+            .title("Mod Bundle Folder")
+            .modal(true)
+            .accept_label("Add")
+            .build();
+
+        dialog.set_initial_folder(path_to_file(&self.initial_folder()).as_ref());
+
+        // Get root window
+        let root = self.root()
+            .and_downcast::<gtk::Window>();
+
+        // Show dialog
+        dialog.select_multiple_folders(root.as_ref(), None::<&gio::Cancellable>, clone!(
+            #[weak(rename_to = files_dialog)] self,
+            move |result| {
+                if let Ok(file_list) = result {
+                    files_dialog.append_files(file_list.iter::<gio::File>()
+                        .flatten()
+                        .map(|file| file_to_path(&file))
+                        .collect()
+                    );
+                }
+            }
+        ));
+    }
+
+    //-----------------------------------
+    // Move file helper function
+    //-----------------------------------
+    fn move_file(&self, from: usize, to: usize) {
+        let mut files = self.files();
+
+        if from == to || from >= files.len() || to >= files.len() {
+            return
+        }
+
+        let file = files.remove(from);
+        files.insert(to, file);
+
+        self.set_files(files);
+        self.update_rows(Some(to));
+    }
+
+    //-----------------------------------
+    // Remove file helper function
+    //-----------------------------------
+    fn remove_file(&self, index: usize) {
+        let mut files = self.files();
+
+        if index >= files.len() {
+            return
+        }
+
+        files.remove(index);
+
+        let focus = (!files.is_empty()).then(|| index.min(files.len() - 1));
+
+        self.set_files(files);
+        self.update_rows(focus);
+    }
+
+    //-----------------------------------
+    // DeHackEd lump tooltip helper function
+    //-----------------------------------
+    fn dehacked_lump_tooltip() -> String {
+        let engine_names = |automatic: bool| {
+            ENGINE_ARRAY.iter()
+                .filter(|data| data.features.contains(EngineFeatures::DEHACKED_LUMPS) == automatic)
+                .map(|data| data.name)
+                .collect::<Vec<&str>>()
+                .join(", ")
+        };
+
+        format!("Contains a DeHackEd patch\nApplied automatically by {}\nLoaded with -dehlump by {}", engine_names(true), engine_names(false))
+    }
+
+    //-----------------------------------
+    // Create row helper function
+    //-----------------------------------
+    fn create_row(&self, index: usize, file: &str) -> adw::ActionRow {
+        let path = Path::new(file);
+
+        let row = adw::ActionRow::builder()
+            .title(path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default())
+            .subtitle(path.parent().map(|folder| folder.display().to_string()).unwrap_or_default())
+            .subtitle_lines(1)
+            .tooltip_text(file)
+            .use_markup(false)
+            .focusable(true)
+            .build();
+
+        // Drag handle and load order
+        row.add_prefix(&gtk::Image::from_icon_name("list-drag-handle-symbolic"));
+
+        row.add_prefix(&gtk::Label::builder()
+            .label((index + 1).to_string())
+            .width_chars(2)
+            .css_classes(["dim-label", "numeric"])
+            .build()
+        );
+
+        // Mod bundle indicator (files loaded in order, or error)
+        if PWadBundle::is_bundle(path) {
+            let (icon, tooltip) = match PWadBundle::open(path) {
+                Ok(bundle) => ("folder-symbolic", bundle.description()),
+                Err(error) => ("dialog-warning-symbolic", format!("Invalid mod bundle: {error}")),
+            };
+
+            row.add_suffix(&gtk::Image::builder()
+                .icon_name(icon)
+                .tooltip_text(tooltip)
+                .build()
+            );
+        }
+
+        // DeHackEd patch indicator (patch file or WAD with embedded patch)
+        let dehacked_tooltip = if PWadBundle::is_bundle(path) {
+            None
+        } else if FileKind::from_path(path) == FileKind::Dehacked {
+            Some(String::from("DeHackEd patch, loaded with -deh"))
+        } else if WadFile::open(path).is_ok_and(|wad| wad.has_lump("DEHACKED")) {
+            Some(Self::dehacked_lump_tooltip())
+        } else {
+            None
+        };
+
+        if let Some(tooltip) = dehacked_tooltip {
+            row.add_suffix(&gtk::Image::builder()
+                .icon_name("text-x-generic-symbolic")
+                .tooltip_text(tooltip)
+                .build()
+            );
+        }
+
+        // Remove button
+        let remove_button = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text("Remove")
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+
+        remove_button.connect_clicked(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |_| {
+                dialog.remove_file(index);
+            }
+        ));
+
+        row.add_suffix(&remove_button);
+
+        // Drag source (row index)
+        let drag_source = gtk::DragSource::new();
+        drag_source.set_actions(gdk::DragAction::MOVE);
+
+        drag_source.connect_prepare(move |_, _, _| {
+            Some(gdk::ContentProvider::for_value(&(index as u32).to_value()))
+        });
+
+        drag_source.connect_drag_begin(clone!(
+            #[weak] row,
+            move |source, _| {
+                source.set_icon(Some(&gtk::WidgetPaintable::new(Some(&row))), 0, 0);
+            }
+        ));
+
+        row.add_controller(drag_source);
+
+        // Drop target (move dragged row here)
+        let drop_target = gtk::DropTarget::new(u32::static_type(), gdk::DragAction::MOVE);
+
+        drop_target.connect_drop(clone!(
+            #[weak(rename_to = dialog)] self,
+            #[upgrade_or]
+            false,
+            move |_, value, _, _| {
+                let Ok(from) = value.get::<u32>() else {
+                    return false
+                };
+
+                dialog.move_file(from as usize, index);
+
+                true
+            }
+        ));
+
+        row.add_controller(drop_target);
+
+        // Keyboard shortcuts (move with Alt+Up/Down, remove with Delete)
+        let shortcuts = gtk::ShortcutController::new();
+
+        for (trigger, action) in [("<Alt>Up", -1), ("<Alt>Down", 1), ("Delete", 0)] {
+            shortcuts.add_shortcut(gtk::Shortcut::new(
+                gtk::ShortcutTrigger::parse_string(trigger),
+                Some(gtk::CallbackAction::new(clone!(
+                    #[weak(rename_to = dialog)] self,
+                    #[upgrade_or]
+                    glib::Propagation::Proceed,
+                    move |_, _| {
+                        match action {
+                            0 => dialog.remove_file(index),
+                            -1 if index > 0 => dialog.move_file(index, index - 1),
+                            1 => dialog.move_file(index, index + 1),
+                            _ => {}
+                        }
+
+                        glib::Propagation::Stop
+                    }
+                )))
+            ));
+        }
+
+        row.add_controller(shortcuts);
+
+        row
+    }
+
+    //-----------------------------------
+    // Update rows function
+    //-----------------------------------
+    // Rebuilds the list, focusing the row at the given index
+    fn update_rows(&self, focus: Option<usize>) {
+        let imp = self.imp();
+
+        for row in imp.rows.take() {
+            imp.list_box.remove(&row);
+        }
+
+        let rows: Vec<adw::ActionRow> = self.files().iter()
+            .enumerate()
+            .map(|(i, file)| self.create_row(i, file))
+            .collect();
+
+        for row in &rows {
+            imp.list_box.append(row);
+        }
+
+        if let Some(row) = focus.and_then(|i| rows.get(i)) {
+            row.grab_focus();
+        }
+
+        imp.clear_button.set_sensitive(!rows.is_empty());
+
+        imp.stack.set_visible_child_name(if rows.is_empty() { "empty" } else { "list" });
+
+        imp.rows.replace(rows);
+    }
+
+    //-----------------------------------
+    // Public append files function
+    //-----------------------------------
+    // Files already in the list are not added again
+    pub fn append_files(&self, new_files: Vec<String>) {
+        let mut files = self.files();
+
+        append_files(&mut files, new_files);
+
+        self.set_files(files);
+        self.update_rows(None);
+    }
+}
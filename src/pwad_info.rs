@@ -0,0 +1,577 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::engine_data::{EngineFeatures, FileKind};
+use crate::iwad_data::{IWadID, IWAD_FILENAMES};
+use crate::wad_reader::ResourceFile;
+
+//------------------------------------------------------------------------------
+// CONST VARIABLES
+//------------------------------------------------------------------------------
+// File extensions listed in the PWAD library
+pub const PWAD_EXTENSIONS: [&str; 4] = ["wad", "pk3", "pk7", "zip"];
+
+// Lumps that may contain a text description of a WAD
+const TEXT_LUMPS: [&str; 3] = ["WADINFO", "README", "INFO"];
+
+// Lumps that may contain map definitions
+const MAPINFO_LUMPS: [&str; 3] = ["MAPINFO", "ZMAPINFO", "UMAPINFO"];
+
+// Lumps only supported by specific engines
+const FEATURE_LUMPS: [(&str, EngineFeatures); 11] = [
+    ("ZSCRIPT", EngineFeatures::ZDOOM),
+    ("DECORATE", EngineFeatures::ZDOOM),
+    ("GLDEFS", EngineFeatures::ZDOOM),
+    ("ZMAPINFO", EngineFeatures::ZDOOM),
+    ("UMAPINFO", EngineFeatures::UMAPINFO),
+    ("TEXTMAP", EngineFeatures::UDMF),
+    ("DEHACKED", EngineFeatures::DEHACKED),
+    ("GAMECONF", EngineFeatures::ID24),
+    ("SBARDEF", EngineFeatures::ID24),
+    ("SKYDEFS", EngineFeatures::ID24),
+    ("TRAKINFO", EngineFeatures::ID24),
+];
+
+// Last vanilla frame and thing numbers (higher ones require DEHEXTRA)
+const VANILLA_FRAMES: u32 = 966;
+const VANILLA_THINGS: u32 = 137;
+
+// Text files larger than this are not read
+const MAX_TEXT_SIZE: u32 = 64 * 1024;
+const MAX_DEHACKED_SIZE: u32 = 4 * 1024 * 1024;
+
+// Games with ExMy maps
+const EPISODIC_GAMES: IWadID = IWadID::DOOM
+    .union(IWadID::UDOOM)
+    .union(IWadID::FREEDOOM1)
+    .union(IWadID::ANY_HERETIC)
+    .union(IWadID::CHEX)
+    .union(IWadID::CHEX3)
+    .union(IWadID::REKKR);
+
+// Games with MAPxx maps
+const NUMBERED_GAMES: IWadID = IWadID::DOOM2
+    .union(IWadID::PLUTONIA)
+    .union(IWadID::TNT)
+    .union(IWadID::FREEDOOM2)
+    .union(IWadID::HEXEN)
+    .union(IWadID::STRIFE)
+    .union(IWadID::HACX)
+    .union(IWadID::HARMONY)
+    .union(IWadID::SQUARE)
+    .union(IWadID::ACTION2)
+    .union(IWadID::HEDON);
+
+// Games using Doom resources (D_xxx music lumps)
+const DOOM_ENGINE_GAMES: IWadID = IWadID::ALL
+    .difference(IWadID::ANY_HERETIC)
+    .difference(IWadID::HEXEN)
+    .difference(IWadID::STRIFE);
+
+// IWAD file names used in GZDoom GAMEINFO lumps
+const GAMEINFO_IWADS: [(&str, IWadID); 10] = [
+    ("doom.wad", IWadID::DOOM.union(IWadID::UDOOM).union(IWadID::FREEDOOM1)),
+    ("doom1.wad", IWadID::DOOM.union(IWadID::UDOOM).union(IWadID::FREEDOOM1)),
+    ("doomu.wad", IWadID::UDOOM.union(IWadID::FREEDOOM1)),
+    ("doom2.wad", IWadID::DOOM2.union(IWadID::PLUTONIA).union(IWadID::TNT).union(IWadID::FREEDOOM2)),
+    ("freedoom1.wad", IWadID::FREEDOOM1),
+    ("freedoom2.wad", IWadID::FREEDOOM2),
+    ("heretic.wad", IWadID::HERETIC),
+    ("hexen.wad", IWadID::HEXEN),
+    ("strife1.wad", IWadID::STRIFE),
+    ("hacx.wad", IWadID::HACX),
+];
+
+//------------------------------------------------------------------------------
+// STATIC VARIABLES
+//------------------------------------------------------------------------------
+// Info read during this session, with the file modification time
+static INFO_CACHE: Mutex<Option<HashMap<PathBuf, (SystemTime, PWadInfo)>>> = Mutex::new(None);
+
+//------------------------------------------------------------------------------
+// STRUCT: PWadInfo
+//------------------------------------------------------------------------------
+#[derive(Debug, Default, Clone)]
+pub struct PWadInfo {
+    pub title: Option<String>,
+    pub game: Option<String>,
+    pub map_count: Option<u32>,
+    pub target: IWadID,
+    pub requires: EngineFeatures,
+}
+
+impl PWadInfo {
+    //-----------------------------------
+    // Text field helper function
+    //-----------------------------------
+    // Returns the value of a "Key : Value" line (idgames text file template)
+    fn text_field(text: &str, key: &str) -> Option<String> {
+        text.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.trim().to_owned())
+            .filter(|value| !value.is_empty())
+    }
+
+    //-----------------------------------
+    // Parse text function
+    //-----------------------------------
+    pub fn parse_text(&mut self, text: &str) {
+        if self.title.is_none() {
+            self.title = Self::text_field(text, "Title");
+        }
+
+        if self.game.is_none() {
+            self.game = Self::text_field(text, "Game");
+        }
+    }
+
+    //-----------------------------------
+    // Is map lump helper function
+    //-----------------------------------
+    // Map markers are named ExMy or MAPxx
+    pub fn is_map_lump(name: &str) -> bool {
+        let bytes = name.as_bytes();
+
+        match bytes {
+            [b'E', e, b'M', m] => e.is_ascii_digit() && m.is_ascii_digit(),
+            [b'M', b'A', b'P', x, y] => x.is_ascii_digit() && y.is_ascii_digit(),
+            _ => false
+        }
+    }
+
+    //-----------------------------------
+    // Lump name helper function
+    //-----------------------------------
+    // Archive files are matched to lumps by upper case name without extension
+    fn lump_name(name: &str) -> String {
+        Path::new(name).file_stem()
+            .map(|stem| stem.to_string_lossy().to_uppercase())
+            .unwrap_or_default()
+    }
+
+    //-----------------------------------
+    // MAPINFO maps helper function
+    //-----------------------------------
+    // Map definitions ("map MAP01 ..." or "map 1 ..." for Hexen) and episode
+    // start maps ("episode E1M1 ..."). Other game hints (gameinfo blocks, sky
+    // or music names) are not used: the map format and GAMEINFO IWAD are
+    // enough to tell games apart
+    pub fn mapinfo_maps(text: &str) -> Vec<String> {
+        text.lines()
+            .filter_map(|line| {
+                let mut tokens = line.split_whitespace();
+
+                tokens.next()
+                    .filter(|token| token.eq_ignore_ascii_case("map") || token.eq_ignore_ascii_case("episode"))
+                    .and(tokens.next())
+                    .map(|name| name.trim_matches('"').to_uppercase())
+            })
+            .collect()
+    }
+
+    //-----------------------------------
+    // GAMEINFO IWAD helper function
+    //-----------------------------------
+    // Games matching the "IWAD = file" line of a GZDoom GAMEINFO lump
+    pub fn gameinfo_iwad(text: &str) -> Option<IWadID> {
+        let iwad = text.lines()
+            .filter_map(|line| line.split_once('='))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case("IWAD"))
+            .map(|(_, value)| value.trim().trim_matches('"').to_lowercase())?;
+
+        GAMEINFO_IWADS.iter()
+            .chain(IWAD_FILENAMES.iter())
+            .find(|(name, _)| *name == iwad)
+            .map(|(_, id)| *id)
+    }
+
+    //-----------------------------------
+    // Target game function
+    //-----------------------------------
+    // Games compatible with the given maps (lump names or MAPINFO entries) and
+    // game-specific lumps; empty if unknown or contradictory
+    pub fn target_game(maps: &[String], lumps: &[String]) -> IWadID {
+        let mut target = IWadID::empty();
+
+        if maps.iter().any(|name| Self::is_map_lump(name) && name.starts_with('E')) {
+            target |= EPISODIC_GAMES;
+
+            // Episode 4 requires The Ultimate Doom
+            if maps.iter().any(|name| name.starts_with("E4")) {
+                target.remove(IWadID::DOOM);
+            }
+        }
+
+        if maps.iter().any(|name| Self::is_map_lump(name) && name.starts_with("MAP")) {
+            target |= NUMBERED_GAMES;
+        }
+
+        let hints = [
+            (maps.iter().any(|name| name.parse::<u32>().is_ok()), IWadID::HEXEN),
+            (lumps.iter().any(|name| name.starts_with("MUS_E")), IWadID::ANY_HERETIC),
+            (lumps.iter().any(|name| name.starts_with("SCRIPT") && name[6..].parse::<u32>().is_ok()), IWadID::STRIFE),
+            (lumps.iter().any(|name| name.starts_with("D_")), DOOM_ENGINE_GAMES),
+        ];
+
+        for (_, games) in hints.iter().filter(|(found, _)| *found) {
+            target = if target.is_empty() { *games } else { target & *games };
+
+            if target.is_empty() {
+                break
+            }
+        }
+
+        target
+    }
+
+    //-----------------------------------
+    // DeHackEd features helper function
+    //-----------------------------------
+    // Features required by a DeHackEd patch (from its version and the frame and
+    // thing numbers it modifies)
+    pub fn dehacked_features(text: &str) -> EngineFeatures {
+        let mut features = EngineFeatures::DEHACKED;
+
+        for line in text.lines().map(str::trim) {
+            if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim();
+
+                if key.eq_ignore_ascii_case("Doom version") {
+                    match value.trim() {
+                        "2021" => features |= EngineFeatures::MBF21,
+                        "2024" => features |= EngineFeatures::ID24,
+                        _ => {}
+                    }
+                } else if key.eq_ignore_ascii_case("MBF21 Bits") {
+                    features |= EngineFeatures::MBF21;
+                }
+            } else {
+                let mut tokens = line.split_whitespace();
+
+                let limit = match tokens.next() {
+                    Some(token) if token.eq_ignore_ascii_case("Frame") => VANILLA_FRAMES,
+                    Some(token) if token.eq_ignore_ascii_case("Thing") => VANILLA_THINGS,
+                    _ => continue
+                };
+
+                if tokens.next().and_then(|number| number.parse::<u32>().ok()).is_some_and(|number| number > limit) {
+                    features |= EngineFeatures::DEHEXTRA;
+                }
+            }
+        }
+
+        features
+    }
+
+    //-----------------------------------
+    // Required features function
+    //-----------------------------------
+    // Engine features required by engine-specific lumps (Hexen format maps are
+    // recognized by BEHAVIOR lumps outside UDMF maps)
+    pub fn required_features(lumps: &[String]) -> EngineFeatures {
+        let mut features = FEATURE_LUMPS.iter()
+            .filter(|(name, _)| lumps.iter().any(|lump| lump == name))
+            .fold(EngineFeatures::empty(), |features, (_, feature)| features | *feature);
+
+        if !features.contains(EngineFeatures::UDMF) && lumps.iter().any(|lump| lump == "BEHAVIOR") {
+            features |= EngineFeatures::HEXEN_MAPS;
+        }
+
+        features
+    }
+
+    //-----------------------------------
+    // Read file helper function
+    //-----------------------------------
+    // WAD maps are counted from map markers, archive maps from the maps/
+    // folder (unknown if the archive holds other WADs); text is read from
+    // description lumps or root folder files
+    fn read_file(&mut self, path: &Path) {
+        let Ok(mut file) = ResourceFile::open(path) else { return };
+
+        let is_archive = matches!(file, ResourceFile::Zip(_));
+
+        let names: Vec<String> = file.entry_names().into_iter()
+            .map(str::to_owned)
+            .collect();
+
+        let lumps: Vec<String> = names.iter()
+            .map(|name| if is_archive { Self::lump_name(name) } else { name.clone() })
+            .collect();
+
+        let mut maps: Vec<String> = if is_archive {
+            names.iter()
+                .filter(|name| {
+                    let name = name.to_lowercase();
+
+                    name.starts_with("maps/") && name.ends_with(".wad")
+                })
+                .map(|name| Self::lump_name(name))
+                .collect()
+        } else {
+            lumps.iter()
+                .filter(|name| Self::is_map_lump(name))
+                .cloned()
+                .collect()
+        };
+
+        // Maps in WADs outside the maps/ folder are not counted (unknown)
+        let has_embedded_wads = is_archive && names.iter().any(|name| {
+            let name = name.to_lowercase();
+
+            !name.starts_with("maps/") && name.ends_with(".wad")
+        });
+
+        if !has_embedded_wads {
+            self.map_count = Some(maps.len() as u32);
+        }
+
+        self.requires = Self::required_features(&lumps);
+
+        // Read text, MAPINFO, GAMEINFO, COMPLVL and DEHACKED lumps (root folder
+        // files in archives)
+        let mut gameinfo_target: Option<IWadID> = None;
+
+        for (name, lump) in names.iter().zip(&lumps) {
+            if is_archive && name.contains('/') {
+                continue
+            }
+
+            let is_text = if is_archive { name.to_lowercase().ends_with(".txt") } else { TEXT_LUMPS.contains(&lump.as_str()) };
+            let is_mapinfo = MAPINFO_LUMPS.contains(&lump.as_str());
+            let is_gameinfo = lump == "GAMEINFO";
+            let is_complvl = lump == "COMPLVL";
+            let is_dehacked = lump == "DEHACKED";
+
+            if !(is_text || is_mapinfo || is_gameinfo || is_complvl || is_dehacked) {
+                continue
+            }
+
+            let max_size = if is_dehacked { MAX_DEHACKED_SIZE } else { MAX_TEXT_SIZE };
+
+            let Ok(data) = file.read(name, max_size) else { continue };
+
+            let text = String::from_utf8_lossy(&data);
+
+            if is_text {
+                self.parse_text(&text);
+            }
+
+            if is_mapinfo {
+                maps.extend(Self::mapinfo_maps(&text));
+            }
+
+            if is_gameinfo && gameinfo_target.is_none() {
+                gameinfo_target = Self::gameinfo_iwad(&text);
+            }
+
+            if is_complvl && text.trim().eq_ignore_ascii_case("mbf21") {
+                self.requires |= EngineFeatures::MBF21;
+            }
+
+            if is_dehacked {
+                self.requires |= Self::dehacked_features(&text);
+            }
+        }
+
+        self.target = gameinfo_target.unwrap_or_else(|| Self::target_game(&maps, &lumps));
+    }
+
+    //-----------------------------------
+    // Read DeHackEd helper function
+    //-----------------------------------
+    // Standalone DeHackEd patch (larger files are not read)
+    fn read_dehacked(&mut self, path: &Path) {
+        if let Some(data) = fs::metadata(path).ok()
+            .filter(|metadata| metadata.len() <= u64::from(MAX_DEHACKED_SIZE))
+            .and_then(|_| fs::read(path).ok())
+        {
+            self.requires |= Self::dehacked_features(&String::from_utf8_lossy(&data));
+        }
+    }
+
+    //-----------------------------------
+    // Read sidecar helper function
+    //-----------------------------------
+    // Text file with the same name next to the PWAD (case-insensitive)
+    fn read_sidecar(&mut self, path: &Path) {
+        let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else { return };
+
+        let sidecar = path.parent()
+            .and_then(|folder| fs::read_dir(folder).ok())
+            .into_iter()
+            .flat_map(|entries| entries.flatten())
+            .map(|entry| entry.path())
+            .find(|file| {
+                file.file_stem().and_then(|name| name.to_str()).is_some_and(|name| name.eq_ignore_ascii_case(stem)) &&
+                    file.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case("txt"))
+            });
+
+        if let Some(text) = sidecar
+            .filter(|file| fs::metadata(file).is_ok_and(|metadata| metadata.len() <= u64::from(MAX_TEXT_SIZE)))
+            .and_then(|file| fs::read(file).ok())
+        {
+            self.parse_text(&String::from_utf8_lossy(&text));
+        }
+    }
+
+    //-----------------------------------
+    // From file function
+    //-----------------------------------
+    // Sidecar text files take precedence over embedded ones; the file format
+    // is detected from its contents
+    pub fn from_file(path: &Path) -> Self {
+        let mut info = Self::default();
+
+        info.read_sidecar(path);
+
+        if FileKind::from_path(path) == FileKind::Dehacked {
+            info.read_dehacked(path);
+        } else {
+            info.read_file(path);
+        }
+
+        info
+    }
+
+    //-----------------------------------
+    // Cached from file function
+    //-----------------------------------
+    // Files are only read again when modified (safe to call from worker threads)
+    pub fn cached_from_file(path: &Path) -> Self {
+        let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+
+        let cached = INFO_CACHE.lock().unwrap_or_else(|error| error.into_inner())
+            .as_ref()
+            .and_then(|cache| cache.get(path))
+            .filter(|(cached, _)| Some(*cached) == modified)
+            .map(|(_, info)| info.clone());
+
+        if let Some(info) = cached {
+            return info
+        }
+
+        let info = Self::from_file(path);
+
+        if let Some(modified) = modified {
+            INFO_CACHE.lock().unwrap_or_else(|error| error.into_inner())
+                .get_or_insert_with(HashMap::new)
+                .insert(path.to_path_buf(), (modified, info.clone()));
+        }
+
+        info
+    }
+}
+
+//------------------------------------------------------------------------------
+// TESTS
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idgames_text_fields_are_parsed() {
+        let mut info = PWadInfo::default();
+
+        info.parse_text("===========\nTitle                   : Scythe 2\nFilename                : scythe2.wad\nGame                    : Doom2\nDescription             : 32 new levels: hard\n");
+
+        assert_eq!(info.title.as_deref(), Some("Scythe 2"));
+        assert_eq!(info.game.as_deref(), Some("Doom2"));
+
+        // Existing values are kept
+        info.parse_text("Title : Other\n");
+
+        assert_eq!(info.title.as_deref(), Some("Scythe 2"));
+    }
+
+    #[test]
+    fn map_lumps_are_recognized() {
+        assert!(PWadInfo::is_map_lump("E1M1"));
+        assert!(PWadInfo::is_map_lump("MAP32"));
+        assert!(!PWadInfo::is_map_lump("MAP1"));
+        assert!(!PWadInfo::is_map_lump("E1M10"));
+        assert!(!PWadInfo::is_map_lump("THINGS"));
+    }
+
+    #[test]
+    fn target_games_are_inferred() {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+
+        // Doom II megawad
+        let target = PWadInfo::target_game(&names(&["MAP01", "MAP02"]), &names(&["MAP01", "THINGS", "D_RUNNIN"]));
+
+        assert!(target.contains(IWadID::DOOM2 | IWadID::FREEDOOM2));
+        assert!(!target.intersects(IWadID::UDOOM | IWadID::HEXEN));
+
+        // Episode 4 maps
+        let target = PWadInfo::target_game(&names(&["E4M1"]), &[]);
+
+        assert!(target.contains(IWadID::UDOOM | IWadID::HERETIC));
+        assert!(!target.contains(IWadID::DOOM));
+
+        // Heretic music
+        assert_eq!(PWadInfo::target_game(&names(&["E1M1"]), &names(&["MUS_E1M1"])), IWadID::ANY_HERETIC);
+
+        // Hexen MAPINFO
+        let maps = PWadInfo::mapinfo_maps("map 1 \"Winnowing Hall\"\nwarptrans 1\n");
+
+        assert_eq!(PWadInfo::target_game(&maps, &[]), IWadID::HEXEN);
+
+        // Episode definitions only (maps defined elsewhere)
+        let maps = PWadInfo::mapinfo_maps("clearepisodes\nepisode E4M1\n{\n\tname = \"Thy Flesh Consumed\"\n}\n");
+
+        assert_eq!(maps, names(&["E4M1"]));
+        assert!(!PWadInfo::target_game(&maps, &[]).contains(IWadID::DOOM));
+
+        // Unknown and contradictory
+        assert!(PWadInfo::target_game(&[], &names(&["PLAYPAL"])).is_empty());
+        assert!(PWadInfo::target_game(&names(&["E1M1"]), &names(&["SCRIPT00"])).is_empty());
+    }
+
+    #[test]
+    fn required_features_are_detected() {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(PWadInfo::required_features(&names(&["MAP01", "THINGS", "ZSCRIPT"])), EngineFeatures::ZDOOM);
+        assert_eq!(PWadInfo::required_features(&names(&["MAP01", "TEXTMAP", "BEHAVIOR", "ENDMAP"])), EngineFeatures::UDMF);
+        assert_eq!(PWadInfo::required_features(&names(&["MAP01", "THINGS", "BEHAVIOR"])), EngineFeatures::HEXEN_MAPS);
+        assert_eq!(PWadInfo::required_features(&names(&["UMAPINFO", "DEHACKED"])), EngineFeatures::UMAPINFO | EngineFeatures::DEHACKED);
+        assert!(PWadInfo::required_features(&names(&["MAP01", "THINGS", "PLAYPAL"])).is_empty());
+    }
+
+    #[test]
+    fn dehacked_features_are_detected() {
+        assert_eq!(PWadInfo::dehacked_features("Patch File for DeHackEd v3.0\nDoom version = 19\nThing 1 (Player)\nFrame 100\n"), EngineFeatures::DEHACKED);
+        assert_eq!(PWadInfo::dehacked_features("Doom version = 2021\n"), EngineFeatures::DEHACKED | EngineFeatures::MBF21);
+        assert_eq!(PWadInfo::dehacked_features("Doom version = 19\nFrame 1100\nThing 150 (Custom)\n"), EngineFeatures::DEHACKED | EngineFeatures::DEHEXTRA);
+        assert_eq!(PWadInfo::dehacked_features("Doom version = 19\nFrame 966\n"), EngineFeatures::DEHACKED);
+        assert_eq!(PWadInfo::dehacked_features("Doom version = 19\nFrame 1000\n"), EngineFeatures::DEHACKED | EngineFeatures::DEHEXTRA);
+        assert_eq!(PWadInfo::dehacked_features("Thing 1\nMBF21 Bits = LOGRAV\n"), EngineFeatures::DEHACKED | EngineFeatures::MBF21);
+    }
+
+    #[test]
+    fn dehacked_files_are_read() {
+        let file = std::env::temp_dir().join(format!("dhxs-launcher-test-{}-patch.deh", std::process::id()));
+
+        fs::write(&file, "Patch File for DeHackEd v3.0\nDoom version = 2021\n").unwrap();
+
+        assert_eq!(PWadInfo::from_file(&file).requires, EngineFeatures::DEHACKED | EngineFeatures::MBF21);
+
+        fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn gameinfo_iwads_are_parsed() {
+        let target = PWadInfo::gameinfo_iwad("IWAD = \"Doom2.wad\"\nStartupTitle = \"Mod\"\n");
+
+        assert!(target.is_some_and(|target| target.contains(IWadID::DOOM2)));
+        assert_eq!(PWadInfo::gameinfo_iwad("iwad = \"hexen.wad\""), Some(IWadID::HEXEN));
+        assert_eq!(PWadInfo::gameinfo_iwad("IWAD = \"unknown.wad\""), None);
+        assert_eq!(PWadInfo::gameinfo_iwad("Load = \"mod.pk3\""), None);
+    }
+}
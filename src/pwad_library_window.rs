@@ -0,0 +1,389 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use gtk::{gio, gdk, glib};
+use adw::subclass::prelude::*;
+use adw::prelude::*;
+use glib::clone;
+
+use crate::pwad_info::{PWadInfo, PWAD_EXTENSIONS};
+use crate::pwad_object::PWadObject;
+
+//------------------------------------------------------------------------------
+// CONST VARIABLES
+//------------------------------------------------------------------------------
+// Maximum folder depth scanned (PWADs are often kept in one folder per mod)
+const MAX_SCAN_DEPTH: usize = 4;
+
+//------------------------------------------------------------------------------
+// MODULE: PWadLibraryWindow
+//------------------------------------------------------------------------------
+mod imp {
+    use super::*;
+
+    //-----------------------------------
+    // Private structure
+    //-----------------------------------
+    #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+    #[properties(wrapper_type = super::PWadLibraryWindow)]
+    #[template(resource = "/com/github/DHXS-Launcher/ui/pwad_library_window.ui")]
+    pub struct PWadLibraryWindow {
+        #[template_child]
+        pub(super) search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub(super) sort_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub(super) stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) empty_page: TemplateChild<adw::StatusPage>,
+        #[template_child]
+        pub(super) list_box: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub(super) filter_model: TemplateChild<gtk::FilterListModel>,
+        #[template_child]
+        pub(super) filter: TemplateChild<gtk::CustomFilter>,
+        #[template_child]
+        pub(super) sorter: TemplateChild<gtk::CustomSorter>,
+        #[template_child]
+        pub(super) model: TemplateChild<gio::ListStore>,
+
+        pub(super) last_played: RefCell<HashMap<String, i64>>,
+        pub(super) scan_id: Cell<u32>,
+
+        #[property(get, set)]
+        pwad_folder: RefCell<String>,
+        #[property(get, set)]
+        selected_files: RefCell<Vec<String>>,
+    }
+
+    //-----------------------------------
+    // Subclass
+    //-----------------------------------
+    #[glib::object_subclass]
+    impl ObjectSubclass for PWadLibraryWindow {
+        const NAME: &'static str = "PWadLibraryWindow";
+        type Type = super::PWadLibraryWindow;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            PWadObject::ensure_type();
+
+            klass.bind_template();
+
+            //---------------------------------------
+            // Add class key bindings
+            //---------------------------------------
+            // Close window binding
+            klass.add_binding_action(gdk::Key::Escape, gdk::ModifierType::NO_MODIFIER_MASK, "window.close");
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PWadLibraryWindow {
+        //-----------------------------------
+        // Constructor
+        //-----------------------------------
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            obj.setup_widgets();
+            obj.setup_signals();
+        }
+    }
+
+    impl WidgetImpl for PWadLibraryWindow {}
+    impl WindowImpl for PWadLibraryWindow {}
+    impl AdwWindowImpl for PWadLibraryWindow {}
+}
+
+//------------------------------------------------------------------------------
+// IMPLEMENTATION: PWadLibraryWindow
+//------------------------------------------------------------------------------
+glib::wrapper! {
+    pub struct PWadLibraryWindow(ObjectSubclass<imp::PWadLibraryWindow>)
+        @extends adw::Window, gtk::Window, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+impl PWadLibraryWindow {
+    //-----------------------------------
+    // Subtitle helper function
+    //-----------------------------------
+    fn subtitle(pwad: &PWadObject) -> String {
+        let filename = pwad.filename();
+
+        let name = Path::new(&filename).file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut details: Vec<String> = vec![glib::format_size(pwad.size()).to_string()];
+
+        if !pwad.game().is_empty() {
+            details.push(pwad.game());
+        }
+
+        match pwad.map_count() {
+            -1 => {},
+            1 => details.push(String::from("1 map")),
+            count => details.push(format!("{count} maps")),
+        }
+
+        let last_played = glib::DateTime::from_unix_local(pwad.last_played()).ok()
+            .filter(|_| pwad.last_played() > 0)
+            .and_then(|date| date.format("%x").ok())
+            .map_or_else(|| String::from("Never played"), |date| format!("Last played {date}"));
+
+        details.push(last_played);
+
+        format!("{name}\n{}", details.join(" \u{2022} "))
+    }
+
+    //-----------------------------------
+    // Create row helper function
+    //-----------------------------------
+    fn create_row(&self, pwad: &PWadObject) -> adw::ActionRow {
+        let row = adw::ActionRow::builder()
+            .title(pwad.title())
+            .subtitle(Self::subtitle(pwad))
+            .tooltip_text(pwad.filename())
+            .use_markup(false)
+            .build();
+
+        // Add to selection button
+        let add_button = gtk::Button::builder()
+            .icon_name("list-add-symbolic")
+            .tooltip_text("Add to Selected PWADs")
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+
+        pwad.bind_property("selected", &add_button, "sensitive")
+            .invert_boolean()
+            .sync_create()
+            .build();
+
+        add_button.connect_clicked(clone!(
+            #[weak(rename_to = window)] self,
+            #[weak] pwad,
+            move |_| {
+                let mut files = window.selected_files();
+
+                files.push(pwad.filename());
+
+                window.set_selected_files(files);
+            }
+        ));
+
+        row.add_suffix(&add_button);
+
+        row
+    }
+
+    //-----------------------------------
+    // Setup widgets
+    //-----------------------------------
+    fn setup_widgets(&self) {
+        let imp = self.imp();
+
+        // Search filter
+        imp.filter.set_filter_func(clone!(
+            #[weak] imp,
+            #[upgrade_or_panic]
+            move |obj| {
+                let pwad = obj.downcast_ref::<PWadObject>()
+                    .expect("Must be a 'PWadObject'");
+
+                pwad.matches(&imp.search_entry.text())
+            }
+        ));
+
+        // Sorter (title ascending, other keys descending)
+        imp.sorter.set_sort_func(clone!(
+            #[weak] imp,
+            #[upgrade_or_panic]
+            move |a, b| {
+                let a = a.downcast_ref::<PWadObject>().expect("Must be a 'PWadObject'");
+                let b = b.downcast_ref::<PWadObject>().expect("Must be a 'PWadObject'");
+
+                let ordering = match imp.sort_dropdown.selected() {
+                    1 => b.size().cmp(&a.size()),
+                    2 => b.map_count().cmp(&a.map_count()),
+                    3 => b.last_played().cmp(&a.last_played()),
+                    _ => std::cmp::Ordering::Equal
+                };
+
+                ordering.then_with(|| a.title().to_lowercase().cmp(&b.title().to_lowercase())).into()
+            }
+        ));
+
+        imp.list_box.bind_model(Some(&imp.filter_model.get()), clone!(
+            #[weak(rename_to = window)] self,
+            #[upgrade_or_panic]
+            move |obj| {
+                let pwad = obj.downcast_ref::<PWadObject>()
+                    .expect("Must be a 'PWadObject'");
+
+                window.create_row(pwad).upcast()
+            }
+        ));
+    }
+
+    //-----------------------------------
+    // Setup signals
+    //-----------------------------------
+    fn setup_signals(&self) {
+        let imp = self.imp();
+
+        // Search entry search changed signal
+        imp.search_entry.connect_search_changed(clone!(
+            #[weak] imp,
+            move |_| {
+                imp.filter.changed(gtk::FilterChange::Different);
+            }
+        ));
+
+        // Sort dropdown selected property notify signal
+        imp.sort_dropdown.connect_selected_notify(clone!(
+            #[weak] imp,
+            move |_| {
+                imp.sorter.changed(gtk::SorterChange::Different);
+            }
+        ));
+
+        // Selected files property notify signal
+        self.connect_selected_files_notify(|window| {
+            window.update_selected();
+        });
+
+        // Window show signal (rescan PWAD folder)
+        self.connect_show(|window| {
+            window.scan_pwad_folder();
+        });
+    }
+
+    //-----------------------------------
+    // Update selected helper function
+    //-----------------------------------
+    fn update_selected(&self) {
+        let selected_files = self.selected_files();
+
+        for pwad in self.imp().model.iter::<PWadObject>().flatten() {
+            pwad.set_selected(selected_files.contains(&pwad.filename()));
+        }
+    }
+
+    //-----------------------------------
+    // Collect files helper function
+    //-----------------------------------
+    fn collect_files(folder: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = fs::read_dir(folder) else { return };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                if depth < MAX_SCAN_DEPTH {
+                    Self::collect_files(&path, depth + 1, files);
+                }
+            } else if path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| PWAD_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    //-----------------------------------
+    // Scan PWAD folder function
+    //-----------------------------------
+    // Files are collected and read in a worker thread (results of previous
+    // scans are discarded)
+    fn scan_pwad_folder(&self) {
+        let imp = self.imp();
+
+        let scan_id = imp.scan_id.get().wrapping_add(1);
+
+        imp.scan_id.set(scan_id);
+
+        if imp.model.n_items() == 0 {
+            imp.stack.set_visible_child_name("scanning");
+        }
+
+        let folder = self.pwad_folder();
+
+        glib::spawn_future_local(clone!(
+            #[weak(rename_to = window)] self,
+            async move {
+                let scan_folder = folder.clone();
+
+                let files = gio::spawn_blocking(move || {
+                    let mut files: Vec<PathBuf> = vec![];
+
+                    Self::collect_files(Path::new(&scan_folder), 0, &mut files);
+
+                    files.into_iter()
+                        .map(|path| {
+                            let size = fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or_default();
+
+                            let info = PWadInfo::cached_from_file(&path);
+
+                            (path, info, size)
+                        })
+                        .collect::<Vec<(PathBuf, PWadInfo, u64)>>()
+                })
+                .await
+                .unwrap_or_default();
+
+                let imp = window.imp();
+
+                if imp.scan_id.get() != scan_id {
+                    return
+                }
+
+                let last_played = imp.last_played.borrow();
+
+                let pwads: Vec<PWadObject> = files.into_iter()
+                    .map(|(path, info, size)| {
+                        let played = last_played.get(&path.display().to_string()).copied().unwrap_or_default();
+
+                        PWadObject::new(&path, info, size, played)
+                    })
+                    .collect();
+
+                imp.model.splice(0, imp.model.n_items(), &pwads);
+
+                window.update_selected();
+
+                imp.empty_page.set_description(Some(&format!("No WAD, PK3, PK7 or ZIP files were found in {folder}")));
+
+                imp.stack.set_visible_child_name(if pwads.is_empty() { "empty" } else { "list" });
+            }
+        ));
+    }
+
+    //-----------------------------------
+    // Public set last played function
+    //-----------------------------------
+    // Launch times (Unix time) by file name
+    pub fn set_last_played(&self, last_played: &HashMap<String, i64>) {
+        self.imp().last_played.replace(last_played.clone());
+    }
+}
+
+impl Default for PWadLibraryWindow {
+    //-----------------------------------
+    // Default constructor
+    //-----------------------------------
+    fn default() -> Self {
+        glib::Object::builder().build()
+    }
+}
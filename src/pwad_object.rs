@@ -0,0 +1,101 @@
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+
+use gtk::glib;
+use gtk::subclass::prelude::*;
+use gtk::prelude::ObjectExt;
+
+use crate::iwad_data::IWadID;
+use crate::pwad_info::PWadInfo;
+
+//------------------------------------------------------------------------------
+// MODULE: PWadObject
+//------------------------------------------------------------------------------
+mod imp {
+    use super::*;
+
+    //-----------------------------------
+    // Private structure
+    //-----------------------------------
+    #[derive(Default, glib::Properties)]
+    #[properties(wrapper_type = super::PWadObject)]
+    pub struct PWadObject {
+        #[property(get, set)]
+        filename: RefCell<String>,
+        #[property(get, set)]
+        title: RefCell<String>,
+        #[property(get, set)]
+        size: Cell<u64>,
+        #[property(get, set)]
+        game: RefCell<String>,
+        #[property(get, set)]
+        map_count: Cell<i32>,
+        #[property(get, set)]
+        last_played: Cell<i64>,
+        #[property(get, set)]
+        selected: Cell<bool>,
+    }
+
+    //-----------------------------------
+    // Subclass
+    //-----------------------------------
+    #[glib::object_subclass]
+    impl ObjectSubclass for PWadObject {
+        const NAME: &'static str = "PWadObject";
+        type Type = super::PWadObject;
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PWadObject {}
+}
+
+//------------------------------------------------------------------------------
+// IMPLEMENTATION: PWadObject
+//------------------------------------------------------------------------------
+glib::wrapper! {
+    pub struct PWadObject(ObjectSubclass<imp::PWadObject>);
+}
+
+impl PWadObject {
+    //-----------------------------------
+    // New function
+    //-----------------------------------
+    // Title defaults to the file name, game to the detected target games; map
+    // count is -1 if unknown
+    pub fn new(path: &Path, info: PWadInfo, size: u64, last_played: i64) -> Self {
+        let title = info.title.unwrap_or_else(|| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+
+        // Standalone games are only listed if no original game matches
+        let target = if info.target.intersects(IWadID::ALL_ORIGINAL) { info.target & IWadID::ALL_ORIGINAL } else { info.target };
+
+        // Build PWadObject
+        glib::Object::builder()
+            .property("filename", path.display().to_string())
+            .property("title", title)
+            .property("size", size)
+            .property("game", info.game.unwrap_or_else(|| target.display_names().join(", ")))
+            .property("map-count", info.map_count.map_or(-1, |count| count as i32))
+            .property("last-played", last_played)
+            .build()
+    }
+
+    //-----------------------------------
+    // Public matches function
+    //-----------------------------------
+    // Case-insensitive search in title and file name
+    pub fn matches(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+
+        let filename = self.filename();
+
+        let name = Path::new(&filename).file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        self.title().to_lowercase().contains(&text) || name.contains(&text)
+    }
+}
@@ -0,0 +1,133 @@
+use gtk::glib;
+use gtk::prelude::*;
+
+use crate::engine_data::EngineSource;
+use crate::iwad_data::IWadID;
+
+//------------------------------------------------------------------------------
+// TYPE: PWadPresetSetting
+//------------------------------------------------------------------------------
+// Preset as stored in gsettings (name, files, game keys, engine source nick)
+pub type PWadPresetSetting = (String, Vec<String>, Vec<String>, String);
+
+//------------------------------------------------------------------------------
+// GLOBAL: Source name function
+//------------------------------------------------------------------------------
+pub fn source_name(source: EngineSource) -> String {
+    glib::EnumValue::from_value(&source.to_value())
+        .map(|(_, value)| value.name().to_owned())
+        .unwrap_or_default()
+}
+
+//------------------------------------------------------------------------------
+// STRUCT: PWadPreset
+//------------------------------------------------------------------------------
+// Named list of PWAD files in load order, optionally restricted to some games
+// and to a source port family
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct PWadPreset {
+    pub name: String,
+    pub files: Vec<String>,
+    pub games: IWadID,
+    pub source: Option<EngineSource>,
+}
+
+impl PWadPreset {
+    //-----------------------------------
+    // New function
+    //-----------------------------------
+    pub fn new(name: &str, files: Vec<String>) -> Self {
+        Self {
+            name: name.to_owned(),
+            files,
+            ..Default::default()
+        }
+    }
+
+    //-----------------------------------
+    // From setting function
+    //-----------------------------------
+    pub fn from_setting((name, files, games, source): PWadPresetSetting) -> Self {
+        let source = glib::EnumClass::new::<EngineSource>().to_value_by_nick(&source)
+            .and_then(|value| value.get::<EngineSource>().ok());
+
+        Self {
+            name,
+            files,
+            games: games.iter()
+                .filter_map(|game| IWadID::from_key(game))
+                .fold(IWadID::empty(), |games, game| games | game),
+            source,
+        }
+    }
+
+    //-----------------------------------
+    // To setting function
+    //-----------------------------------
+    pub fn to_setting(&self) -> PWadPresetSetting {
+        let source = self.source
+            .and_then(|source| {
+                glib::EnumValue::from_value(&source.to_value())
+                    .map(|(_, value)| value.nick().to_owned())
+            })
+            .unwrap_or_default();
+
+        (self.name.clone(), self.files.clone(), self.games.keys(), source)
+    }
+
+    //-----------------------------------
+    // Matches function
+    //-----------------------------------
+    // Whether the preset can be used with the selected game and engine (if any)
+    pub fn matches(&self, game: Option<IWadID>, source: Option<EngineSource>) -> bool {
+        let game_matches = self.games.is_empty() || game.is_none_or(|game| self.games.contains(game));
+        let source_matches = self.source.is_none() || source.is_none() || self.source == source;
+
+        game_matches && source_matches
+    }
+
+    //-----------------------------------
+    // Description function
+    //-----------------------------------
+    // File count, target games and source port family
+    pub fn description(&self) -> String {
+        let mut parts = vec![match self.files.len() {
+            1 => String::from("1 file"),
+            n => format!("{n} files"),
+        }];
+
+        let games = self.games.display_names();
+
+        if !games.is_empty() {
+            parts.push(games.join(", "));
+        }
+
+        if let Some(source) = self.source {
+            parts.push(source_name(source));
+        }
+
+        parts.join(" \u{2022} ")
+    }
+}
+
+//------------------------------------------------------------------------------
+// TESTS
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_are_stored_by_game_key() {
+        let preset = PWadPreset {
+            games: IWadID::DOOM2 | IWadID::FREEDOOM2,
+            source: Some(EngineSource::ZDoom),
+            ..PWadPreset::new("Test", vec![String::from("a.wad")])
+        };
+
+        let setting = preset.to_setting();
+
+        assert_eq!(setting.2, vec![String::from("DOOM2"), String::from("FREEDOOM2")]);
+        assert_eq!(PWadPreset::from_setting(setting), preset);
+    }
+}
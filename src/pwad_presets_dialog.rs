@@ -0,0 +1,375 @@
+use std::cell::RefCell;
+
+use gtk::glib;
+use adw::subclass::prelude::*;
+use adw::prelude::*;
+use glib::clone;
+
+use crate::engine_data::EngineSource;
+use crate::iwad_data::IWadID;
+use crate::pwad_files_dialog::PWadFilesDialog;
+use crate::pwad_preset::{source_name, PWadPreset};
+
+//------------------------------------------------------------------------------
+// MODULE: PWadPresetsDialog
+//------------------------------------------------------------------------------
+mod imp {
+    use super::*;
+
+    //-----------------------------------
+    // Private structure
+    //-----------------------------------
+    #[derive(Default, gtk::CompositeTemplate, glib::Properties)]
+    #[properties(wrapper_type = super::PWadPresetsDialog)]
+    #[template(resource = "/com/github/DHXS-Launcher/ui/pwad_presets_dialog.ui")]
+    pub struct PWadPresetsDialog {
+        #[template_child]
+        pub(super) stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub(super) list_box: TemplateChild<gtk::ListBox>,
+
+        pub(super) rows: RefCell<Vec<adw::ExpanderRow>>,
+        pub(super) presets: RefCell<Vec<PWadPreset>>,
+
+        #[property(get, set)]
+        initial_folder: RefCell<String>,
+    }
+
+    //-----------------------------------
+    // Subclass
+    //-----------------------------------
+    #[glib::object_subclass]
+    impl ObjectSubclass for PWadPresetsDialog {
+        const NAME: &'static str = "PWadPresetsDialog";
+        type Type = super::PWadPresetsDialog;
+        type ParentType = adw::Dialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PWadPresetsDialog {
+        //-----------------------------------
+        // Constructor
+        //-----------------------------------
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            self.obj().update_rows();
+        }
+    }
+
+    impl WidgetImpl for PWadPresetsDialog {}
+    impl AdwDialogImpl for PWadPresetsDialog {}
+}
+
+//------------------------------------------------------------------------------
+// IMPLEMENTATION: PWadPresetsDialog
+//------------------------------------------------------------------------------
+glib::wrapper! {
+    pub struct PWadPresetsDialog(ObjectSubclass<imp::PWadPresetsDialog>)
+        @extends adw::Dialog, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::ShortcutManager;
+}
+
+impl PWadPresetsDialog {
+    //-----------------------------------
+    // Game choices helper function
+    //-----------------------------------
+    // Games with the same name (e.g. Doom and The Ultimate Doom) are grouped
+    fn game_choices() -> Vec<(String, IWadID)> {
+        let flags_class = glib::FlagsClass::new::<IWadID>();
+
+        let mut choices: Vec<(String, IWadID)> = vec![];
+
+        for value in flags_class.values() {
+            let id = IWadID::from_bits_truncate(value.value());
+
+            match choices.iter_mut().find(|(name, _)| name == value.name()) {
+                Some((_, games)) => *games |= id,
+                None => choices.push((value.name().to_owned(), id)),
+            }
+        }
+
+        choices
+    }
+
+    //-----------------------------------
+    // Source choices helper function
+    //-----------------------------------
+    fn source_choices() -> Vec<EngineSource> {
+        let enum_class = glib::EnumClass::new::<EngineSource>();
+
+        enum_class.values().iter()
+            .filter_map(|value| value.to_value(&enum_class).get::<EngineSource>().ok())
+            .collect()
+    }
+
+    //-----------------------------------
+    // Update preset helper function
+    //-----------------------------------
+    fn update_preset(&self, index: usize, update: impl FnOnce(&mut PWadPreset)) {
+        let imp = self.imp();
+
+        let mut presets = imp.presets.borrow_mut();
+
+        let Some(preset) = presets.get_mut(index) else { return };
+
+        update(preset);
+
+        if let Some(row) = imp.rows.borrow().get(index) {
+            row.set_title(&preset.name);
+            row.set_subtitle(&preset.description());
+        }
+    }
+
+    //-----------------------------------
+    // Remove preset helper function
+    //-----------------------------------
+    fn remove_preset(&self, index: usize) {
+        let imp = self.imp();
+
+        if index < imp.presets.borrow().len() {
+            imp.presets.borrow_mut().remove(index);
+
+            self.update_rows();
+        }
+    }
+
+    //-----------------------------------
+    // Create row helper function
+    //-----------------------------------
+    fn create_row(&self, index: usize, preset: &PWadPreset) -> adw::ExpanderRow {
+        let row = adw::ExpanderRow::builder()
+            .title(preset.name.as_str())
+            .subtitle(preset.description())
+            .use_markup(false)
+            .build();
+
+        // Apply, append and remove buttons
+        for (icon, tooltip, action) in [
+            ("document-open-symbolic", "Use as PWAD Files", "win.apply-pwad-preset"),
+            ("list-add-symbolic", "Add to PWAD Files", "win.append-pwad-preset"),
+        ] {
+            let button = gtk::Button::builder()
+                .icon_name(icon)
+                .tooltip_text(tooltip)
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .action_name(action)
+                .action_target(&(index as u32).to_variant())
+                .build();
+
+            row.add_suffix(&button);
+        }
+
+        let remove_button = gtk::Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text("Remove Preset")
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+
+        remove_button.connect_clicked(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |_| {
+                dialog.remove_preset(index);
+            }
+        ));
+
+        row.add_suffix(&remove_button);
+
+        // Name row
+        let name_row = adw::EntryRow::builder()
+            .title("Name")
+            .text(preset.name.as_str())
+            .show_apply_button(true)
+            .build();
+
+        name_row.connect_apply(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |name_row| {
+                let name = name_row.text().trim().to_owned();
+
+                if !name.is_empty() {
+                    dialog.update_preset(index, |preset| preset.name = name);
+                }
+            }
+        ));
+
+        row.add_row(&name_row);
+
+        // Game row (first item for any game)
+        let game_choices = Self::game_choices();
+
+        let game_names: Vec<&str> = ["Any Game"].into_iter()
+            .chain(game_choices.iter().map(|(name, _)| name.as_str()))
+            .collect();
+
+        let game_row = adw::ComboRow::builder()
+            .title("Game")
+            .model(&gtk::StringList::new(&game_names))
+            .build();
+
+        game_row.set_selected(game_choices.iter()
+            .position(|(_, games)| *games == preset.games)
+            .map_or(0, |i| i as u32 + 1)
+        );
+
+        game_row.connect_selected_notify(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |game_row| {
+                let games = (game_row.selected() as usize).checked_sub(1)
+                    .and_then(|i| game_choices.get(i))
+                    .map(|(_, games)| *games)
+                    .unwrap_or_default();
+
+                dialog.update_preset(index, |preset| preset.games = games);
+            }
+        ));
+
+        row.add_row(&game_row);
+
+        // Source port family row (first item for any family)
+        let sources = Self::source_choices();
+
+        let source_names: Vec<String> = sources.iter()
+            .map(|source| source_name(*source))
+            .collect();
+
+        let source_labels: Vec<&str> = ["Any Source Port"].into_iter()
+            .chain(source_names.iter().map(String::as_str))
+            .collect();
+
+        let source_row = adw::ComboRow::builder()
+            .title("Source Port Family")
+            .model(&gtk::StringList::new(&source_labels))
+            .build();
+
+        source_row.set_selected(preset.source
+            .and_then(|source| sources.iter().position(|s| *s == source))
+            .map_or(0, |i| i as u32 + 1)
+        );
+
+        source_row.connect_selected_notify(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |source_row| {
+                let source = (source_row.selected() as usize).checked_sub(1)
+                    .and_then(|i| sources.get(i))
+                    .copied();
+
+                dialog.update_preset(index, |preset| preset.source = source);
+            }
+        ));
+
+        row.add_row(&source_row);
+
+        // Files row (edit file list)
+        let files_row = adw::ActionRow::builder()
+            .title("Files")
+            .activatable(true)
+            .build();
+
+        files_row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+
+        files_row.connect_activated(clone!(
+            #[weak(rename_to = dialog)] self,
+            move |_| {
+                let files = dialog.imp().presets.borrow().get(index)
+                    .map(|preset| preset.files.clone())
+                    .unwrap_or_default();
+
+                let files_dialog = PWadFilesDialog::new(files, &dialog.initial_folder());
+
+                files_dialog.connect_files_notify(clone!(
+                    #[weak] dialog,
+                    move |files_dialog| {
+                        dialog.update_preset(index, |preset| preset.files = files_dialog.files());
+                    }
+                ));
+
+                files_dialog.present(Some(&dialog));
+            }
+        ));
+
+        row.add_row(&files_row);
+
+        row
+    }
+
+    //-----------------------------------
+    // Update rows function
+    //-----------------------------------
+    fn update_rows(&self) {
+        let imp = self.imp();
+
+        for row in imp.rows.take() {
+            imp.list_box.remove(&row);
+        }
+
+        let rows: Vec<adw::ExpanderRow> = imp.presets.borrow().iter()
+            .enumerate()
+            .map(|(i, preset)| self.create_row(i, preset))
+            .collect();
+
+        for row in &rows {
+            imp.list_box.append(row);
+        }
+
+        imp.stack.set_visible_child_name(if rows.is_empty() { "empty" } else { "list" });
+
+        imp.rows.replace(rows);
+    }
+
+    //-----------------------------------
+    // Public presets function
+    //-----------------------------------
+    pub fn presets(&self) -> Vec<PWadPreset> {
+        self.imp().presets.borrow().clone()
+    }
+
+    //-----------------------------------
+    // Public set presets function
+    //-----------------------------------
+    pub fn set_presets(&self, presets: Vec<PWadPreset>) {
+        self.imp().presets.replace(presets);
+
+        self.update_rows();
+    }
+
+    //-----------------------------------
+    // Public save preset function
+    //-----------------------------------
+    // Replaces the files of an existing preset with the same name, keeping its
+    // game and source port family
+    pub fn save_preset(&self, name: &str, files: Vec<String>) {
+        let imp = self.imp();
+
+        let index = imp.presets.borrow().iter()
+            .position(|preset| preset.name == name);
+
+        match index {
+            Some(index) => self.update_preset(index, |preset| preset.files = files),
+            None => {
+                imp.presets.borrow_mut().push(PWadPreset::new(name, files));
+
+                self.update_rows();
+            }
+        }
+    }
+}
+
+impl Default for PWadPresetsDialog {
+    //-----------------------------------
+    // Default constructor
+    //-----------------------------------
+    fn default() -> Self {
+        glib::Object::builder().build()
+    }
+}
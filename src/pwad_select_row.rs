@@ -1,13 +1,14 @@
 use std::cell::{Cell, RefCell};
 use std::path::Path;
 
-use adw::prelude::ActionRowExt;
-use gtk::{gio, glib};
+use adw::prelude::{ActionRowExt, AdwDialogExt};
+use gtk::glib;
 use adw::subclass::prelude::*;
 use gtk::prelude::*;
 use glib::clone;
 
-use crate::utils::{file_to_path, path_to_file};
+use crate::pwad_files_dialog::PWadFilesDialog;
+use crate::utils::append_files;
 
 //------------------------------------------------------------------------------
 // MODULE: PWadSelectRow
@@ -148,58 +149,16 @@ impl PWadSelectRow {
             }
         ));
 
-        // Select button clicked signal
-        self.connect_activated(clone!(
-            move |row| {
-                // Create dialog
-                let dialog = gtk::FileDialog::builder()
-                    .title("PWAD Files")
-                    .modal(true)
-                    .accept_label("Select")
-                    .build();
-
-                // Set filters for dialog
-                let all_filter = gtk::FileFilter::new();
-                all_filter.set_name(Some("All Files"));
-                all_filter.add_pattern("*");
-
-                let pwad_filter = gtk::FileFilter::new();
-                pwad_filter.set_name(Some("WAD/PK3/PK7 files"));
-                pwad_filter.add_mime_type("application/x-doom-wad");
-                pwad_filter.add_mime_type("application/zip");
-                pwad_filter.add_mime_type("application/x-7z-compressed");
-
-                dialog.set_default_filter(Some(&pwad_filter));
-                dialog.set_filters(Some(&gio::ListStore::from_iter([pwad_filter, all_filter])));
-
-                // Set initial location for dialog
-                let files = row.files();
+        // Row activated signal (edit file list)
+        self.connect_activated(|row| {
+            let dialog = PWadFilesDialog::new(row.files(), &row.initial_folder());
 
-                if files.is_empty() {
-                    dialog.set_initial_folder(path_to_file(row.initial_folder().as_ref()).as_ref());
-                } else {
-                    dialog.set_initial_file(path_to_file(&files[0]).as_ref());
-                }
-
-                // Get root window
-                let root = row.root()
-                    .and_downcast::<gtk::Window>();
-
-                // Show dialog
-                dialog.open_multiple(root.as_ref(), None::<&gio::Cancellable>, clone!(
-                    #[weak] row,
-                    move |result| {
-                        if let Ok(file_list) = result {
-                            row.set_files(file_list.iter::<gio::File>()
-                                .flatten()
-                                .map(|file| file_to_path(&file))
-                                .collect::<Vec<String>>()
-                            );
-                        }
-                    }
-                ));
-            }
-        ));
+            dialog.bind_property("files", row, "files")
+                .bidirectional()
+                .build();
+
+            dialog.present(Some(row));
+        });
 
         // Reset button clicked signal
         imp.reset_button.connect_clicked(clone!(
@@ -210,6 +169,18 @@ impl PWadSelectRow {
         ));
     }
 
+    //-----------------------------------
+    // Public append files function
+    //-----------------------------------
+    // Files already selected are not added again
+    pub fn append_files(&self, new_files: Vec<String>) {
+        let mut files = self.files();
+
+        append_files(&mut files, new_files);
+
+        self.set_files(files);
+    }
+
     //-----------------------------------
     // Public reset_to_default function
     //-----------------------------------
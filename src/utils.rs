@@ -1,8 +1,9 @@
 use std::borrow::Cow;
 use std::{fs, io};
 use std::io::Read;
+use std::path::{Path, PathBuf};
 
-use gtk::gio;
+use gtk::{gio, glib};
 use gio::prelude::FileExt;
 
 use crc32fast::Hasher;
@@ -36,6 +37,32 @@ pub fn crc32(file: &str) -> io::Result<u32> {
     Ok(result)
 }
 
+//---------------------------------------
+// Checksum function (MD5, SHA-1, ...)
+//---------------------------------------
+pub fn checksum(file: &str, checksum_type: glib::ChecksumType) -> io::Result<String> {
+    let file = fs::File::open(file)?;
+
+    let mut buffer = [0; 4096]; // buffer size: 4KB
+    let mut reader = io::BufReader::new(file);
+
+    let mut checksum = glib::Checksum::new(checksum_type)
+        .ok_or_else(|| io::Error::other("Unsupported checksum type"))?;
+
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+
+        if bytes_read == 0 {
+            break;
+        }
+
+        checksum.update(&buffer[..bytes_read]);
+    }
+
+    checksum.string()
+        .ok_or_else(|| io::Error::other("Could not compute checksum"))
+}
+
 //---------------------------------------
 // Env expand function
 //---------------------------------------
@@ -60,3 +87,29 @@ pub fn file_to_path(file: &gio::File) -> String {
         .map(|path| path.display().to_string())
         .unwrap_or_default()
 }
+
+//---------------------------------------
+// Unique path function
+//---------------------------------------
+// Returns "name (n).ext" for the first n not already in use
+pub fn unique_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+
+    (1..)
+        .map(|n| path.with_file_name(format!("{stem} ({n}){extension}")))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+//---------------------------------------
+// Append files function
+//---------------------------------------
+// Appends new files to a file list, skipping empty and duplicate entries
+pub fn append_files(files: &mut Vec<String>, new_files: Vec<String>) {
+    for file in new_files {
+        if !file.is_empty() && !files.contains(&file) {
+            files.push(file);
+        }
+    }
+}
@@ -0,0 +1,459 @@
+use std::cell::OnceCell;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use gtk::{gio, glib};
+
+use crate::engine_data::EngineFeatures;
+use crate::iwad_data::{IWadData, IWadID, IWadMatch};
+use crate::pwad_data::{PWadData, PWadType};
+use crate::utils::checksum;
+
+//------------------------------------------------------------------------------
+// CONST VARIABLES
+//------------------------------------------------------------------------------
+const DATABASE_VERSION: i32 = 1;
+
+const DATABASE_RESOURCE: &str = "/com/github/DHXS-Launcher/wad-database.ini";
+const USER_DATABASE_FILE: &str = "dhxs-launcher/wad-database.ini";
+
+static DATABASE: OnceLock<WadDatabase> = OnceLock::new();
+
+//------------------------------------------------------------------------------
+// STRUCT: WadDatabase
+//------------------------------------------------------------------------------
+#[derive(Debug, Default)]
+pub struct WadDatabase {
+    iwads: Vec<(String, IWadData)>,
+    pwads: Vec<(String, PWadData)>,
+    errors: Vec<String>,
+}
+
+impl WadDatabase {
+    //-----------------------------------
+    // Get function
+    //-----------------------------------
+    pub fn get() -> &'static Self {
+        DATABASE.get_or_init(|| {
+            let mut database = Self::default();
+
+            // Load built-in database from resources
+            match gio::resources_lookup_data(DATABASE_RESOURCE, gio::ResourceLookupFlags::NONE) {
+                Ok(bytes) => {
+                    database.load_data(&String::from_utf8_lossy(&bytes), "Built-in database");
+                },
+                Err(error) => {
+                    database.errors.push(format!("Built-in database: {error}"));
+                }
+            }
+
+            // Load user database (overrides and extensions)
+            if let Some(path) = xdg::BaseDirectories::new().find_config_file(USER_DATABASE_FILE) {
+                let source = path.display().to_string();
+
+                match fs::read_to_string(&path) {
+                    Ok(data) => {
+                        database.load_data(&data, &source);
+                    },
+                    Err(error) => {
+                        database.errors.push(format!("{source}: {error}"));
+                    }
+                }
+            }
+
+            database.validate();
+
+            database
+        })
+    }
+
+    //-----------------------------------
+    // Key helper functions
+    //-----------------------------------
+    fn required_key(keyfile: &glib::KeyFile, group: &str, key: &str) -> Result<String, String> {
+        keyfile.string(group, key)
+            .map(String::from)
+            .map_err(|_| format!("missing {key} key"))
+    }
+
+    fn game_key(keyfile: &glib::KeyFile, group: &str) -> Result<IWadID, String> {
+        let game = Self::required_key(keyfile, group, "Game")?;
+
+        IWadID::from_key(&game)
+            .ok_or_else(|| format!("unknown game '{game}'"))
+    }
+
+    fn games_key(keyfile: &glib::KeyFile, group: &str) -> Result<IWadID, String> {
+        let games = keyfile.string_list(group, "Game")
+            .map_err(|_| String::from("missing Game key"))?;
+
+        games.iter().try_fold(IWadID::empty(), |id, game| {
+            IWadID::from_key(game.as_str())
+                .map(|game_id| id | game_id)
+                .ok_or_else(|| format!("unknown game '{game}'"))
+        })
+    }
+
+    fn type_key(keyfile: &glib::KeyFile, group: &str) -> Result<PWadType, String> {
+        keyfile.string(group, "Type").ok()
+            .map_or(Ok(PWadType::default()), |kind| {
+                PWadType::from_key(&kind)
+                    .ok_or_else(|| format!("unknown type '{kind}'"))
+            })
+    }
+
+    fn features_key(keyfile: &glib::KeyFile, group: &str) -> Result<EngineFeatures, String> {
+        keyfile.string_list(group, "Requires").unwrap_or_default().iter()
+            .try_fold(EngineFeatures::empty(), |features, feature| {
+                EngineFeatures::from_key(feature.as_str())
+                    .map(|feature_id| features | feature_id)
+                    .ok_or_else(|| format!("unknown engine feature '{feature}'"))
+            })
+    }
+
+    fn file_key(keyfile: &glib::KeyFile, group: &str) -> Result<Option<glob::Pattern>, String> {
+        keyfile.string(group, "File").ok()
+            .map(|file| glob::Pattern::new(&file).map_err(|_| format!("invalid File pattern '{file}'")))
+            .transpose()
+    }
+
+    fn crc32_key(keyfile: &glib::KeyFile, group: &str) -> Result<u32, String> {
+        let crc = Self::required_key(keyfile, group, "CRC32")?;
+
+        u32::from_str_radix(crc.trim_start_matches("0x"), 16)
+            .map_err(|_| format!("invalid CRC32 '{crc}'"))
+    }
+
+    fn optional_crc32_key(keyfile: &glib::KeyFile, group: &str) -> Result<Option<u32>, String> {
+        keyfile.has_key(group, "CRC32").unwrap_or_default()
+            .then(|| Self::crc32_key(keyfile, group))
+            .transpose()
+    }
+
+    fn size_key(keyfile: &glib::KeyFile, group: &str) -> Result<Option<u64>, String> {
+        keyfile.string(group, "Size").ok()
+            .map(|size| size.parse::<u64>().map_err(|_| format!("invalid Size '{size}'")))
+            .transpose()
+    }
+
+    fn bool_key(keyfile: &glib::KeyFile, group: &str, key: &str) -> Result<bool, String> {
+        keyfile.string(group, key).ok()
+            .map(|value| match value.as_str() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(format!("invalid {key} '{value}'"))
+            })
+            .transpose()
+            .map(Option::unwrap_or_default)
+    }
+
+    fn digest_key(keyfile: &glib::KeyFile, group: &str, key: &str, len: usize) -> Result<Option<String>, String> {
+        keyfile.string(group, key).ok()
+            .map(|digest| {
+                if digest.len() == len && digest.chars().all(|c| c.is_ascii_hexdigit()) {
+                    Ok(digest.to_lowercase())
+                } else {
+                    Err(format!("invalid {key} '{digest}'"))
+                }
+            })
+            .transpose()
+    }
+
+    //-----------------------------------
+    // Parse entry functions
+    //-----------------------------------
+    fn parse_iwad(keyfile: &glib::KeyFile, group: &str) -> Result<IWadData, String> {
+        let data = IWadData {
+            group: group.to_owned(),
+            id: Self::game_key(keyfile, group)?,
+            name: Self::required_key(keyfile, group, "Name")?,
+            version: Self::required_key(keyfile, group, "Version")?,
+            crc32: Self::optional_crc32_key(keyfile, group)?,
+            size: Self::size_key(keyfile, group)?,
+            md5: Self::digest_key(keyfile, group, "MD5", 32)?,
+            sha1: Self::digest_key(keyfile, group, "SHA1", 40)?,
+            canonical: Self::bool_key(keyfile, group, "Canonical")?,
+        };
+
+        // Entries without CRC-32 are identified by size and digest
+        if data.crc32.is_none() && (data.size.is_none() || (data.md5.is_none() && data.sha1.is_none())) {
+            return Err(String::from("CRC32 key, or Size and MD5/SHA1 keys required"))
+        }
+
+        Ok(data)
+    }
+
+    fn parse_pwad(keyfile: &glib::KeyFile, group: &str) -> Result<PWadData, String> {
+        let data = PWadData {
+            group: group.to_owned(),
+            id: Self::games_key(keyfile, group)?,
+            name: Self::required_key(keyfile, group, "Name")?,
+            description: Self::required_key(keyfile, group, "Description")?,
+            crc32: Self::optional_crc32_key(keyfile, group)?,
+            file: Self::file_key(keyfile, group)?,
+            kind: Self::type_key(keyfile, group)?,
+            requires: Self::features_key(keyfile, group)?,
+            warp: keyfile.string(group, "Warp").ok().map(String::from),
+            expansion: Self::bool_key(keyfile, group, "Expansion")?,
+        };
+
+        // Entries without CRC-32 are identified by file name
+        if data.crc32.is_none() && data.file.is_none() {
+            return Err(String::from("CRC32 or File key required"))
+        }
+
+        Ok(data)
+    }
+
+    //-----------------------------------
+    // Insert entry helper function
+    //-----------------------------------
+    fn insert_entry<T>(entries: &mut Vec<(String, T)>, group: &str, data: T) {
+        if let Some(entry) = entries.iter_mut().find(|(name, _)| name == group) {
+            entry.1 = data;
+        } else {
+            entries.push((group.to_owned(), data));
+        }
+    }
+
+    //-----------------------------------
+    // Load data function
+    //-----------------------------------
+    fn load_data(&mut self, data: &str, source: &str) {
+        let keyfile = glib::KeyFile::new();
+
+        if let Err(error) = keyfile.load_from_data(data, glib::KeyFileFlags::NONE) {
+            self.errors.push(format!("{source}: {error}"));
+            return
+        }
+
+        // Check database version
+        match keyfile.integer("Database", "Version") {
+            Ok(version) if version <= DATABASE_VERSION => {},
+            Ok(version) => {
+                self.errors.push(format!("{source}: unsupported database version {version}"));
+                return
+            },
+            Err(_) => {
+                self.errors.push(format!("{source}: missing [Database] Version key"));
+                return
+            }
+        }
+
+        // Parse entries
+        for group in keyfile.groups().iter() {
+            let group = group.as_str();
+
+            let result = if group == "Database" {
+                Ok(())
+            } else if group.starts_with("IWAD ") {
+                Self::parse_iwad(&keyfile, group)
+                    .map(|data| Self::insert_entry(&mut self.iwads, group, data))
+            } else if group.starts_with("PWAD ") {
+                Self::parse_pwad(&keyfile, group)
+                    .map(|data| Self::insert_entry(&mut self.pwads, group, data))
+            } else {
+                Err(String::from("unknown entry type (expected IWAD or PWAD)"))
+            };
+
+            if let Err(error) = result {
+                self.errors.push(format!("{source} [{group}]: {error}"));
+            }
+        }
+    }
+
+    //-----------------------------------
+    // Validate functions
+    //-----------------------------------
+    fn differs<T: PartialEq>(a: Option<T>, b: Option<T>) -> bool {
+        a.zip(b).is_some_and(|(a, b)| a != b)
+    }
+
+    fn validate(&mut self) {
+        // Entries must be told apart by CRC-32, size or digest
+        for (i, (group_a, data_a)) in self.iwads.iter().enumerate() {
+            for (group_b, data_b) in self.iwads.iter().skip(i + 1) {
+                let distinct = Self::differs(data_a.crc32, data_b.crc32) ||
+                    Self::differs(data_a.size, data_b.size) ||
+                    Self::differs(data_a.md5.as_deref(), data_b.md5.as_deref()) ||
+                    Self::differs(data_a.sha1.as_deref(), data_b.sha1.as_deref());
+
+                if !distinct {
+                    self.errors.push(format!("[{group_a}] and [{group_b}]: ambiguous entries (add Size, MD5 or SHA1 keys)"));
+                }
+            }
+        }
+    }
+
+    //-----------------------------------
+    // Public errors function
+    //-----------------------------------
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    //-----------------------------------
+    // Public find PWAD function
+    //-----------------------------------
+    pub fn find_pwad(&self, filename: &str, hash: u32) -> Option<&PWadData> {
+        let name = Path::new(filename).file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+
+        // Match by CRC-32 first, then by file name for entries without one
+        self.pwads.iter()
+            .map(|(_, data)| data)
+            .find(|data| data.crc32 == Some(hash))
+            .or_else(|| {
+                self.pwads.iter()
+                    .map(|(_, data)| data)
+                    .find(|data| data.crc32.is_none() && data.matches_filename(&name))
+            })
+    }
+
+    //---------------------------------------
+    // Digest matches helper function
+    //---------------------------------------
+    fn digest_matches(filename: &str, expected: Option<&str>, digest: &OnceCell<Option<String>>, checksum_type: glib::ChecksumType) -> bool {
+        // Digests are computed lazily, only when an entry provides one
+        expected.is_none_or(|expected| {
+            digest.get_or_init(|| checksum(filename, checksum_type).ok())
+                .as_deref()
+                .is_some_and(|digest| digest.eq_ignore_ascii_case(expected))
+        })
+    }
+
+    //-----------------------------------
+    // Public identify IWAD function
+    //-----------------------------------
+    pub fn identify_iwad(&self, filename: &str, hash: u32) -> IWadMatch<'_> {
+        let Ok(size) = fs::metadata(filename).map(|metadata| metadata.len()) else {
+            return IWadMatch::Unknown
+        };
+
+        let md5 = OnceCell::new();
+        let sha1 = OnceCell::new();
+
+        let is_match = |data: &IWadData| {
+            Self::digest_matches(filename, data.md5.as_deref(), &md5, glib::ChecksumType::Md5) &&
+                Self::digest_matches(filename, data.sha1.as_deref(), &sha1, glib::ChecksumType::Sha1)
+        };
+
+        // Entries with matching CRC-32, narrowed down by file size then digests
+        let crc_matches: Vec<&IWadData> = self.iwads.iter()
+            .map(|(_, data)| data)
+            .filter(|data| data.crc32 == Some(hash))
+            .collect();
+
+        if let Some(&data) = crc_matches.iter()
+            .filter(|data| data.size.is_none_or(|data_size| data_size == size))
+            .find(|data| is_match(data))
+        {
+            return IWadMatch::Identified(data)
+        }
+
+        // CRC-32 matches but size or stronger hash does not
+        if let Some(&data) = crc_matches.first() {
+            return IWadMatch::Corrupted(data)
+        }
+
+        // Entries without CRC-32 (or a wrong one) identified by size then digests
+        let size_matches: Vec<&IWadData> = self.iwads.iter()
+            .map(|(_, data)| data)
+            .filter(|data| data.size == Some(size))
+            .collect();
+
+        if let Some(&data) = size_matches.iter()
+            .filter(|data| data.md5.is_some() || data.sha1.is_some())
+            .find(|data| is_match(data))
+        {
+            return IWadMatch::Identified(data)
+        }
+
+        // Same size as a known IWAD but different checksums
+        size_matches.first()
+            .map_or(IWadMatch::Unknown, |&data| IWadMatch::Corrupted(data))
+    }
+}
+
+//------------------------------------------------------------------------------
+// TESTS
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_database_is_valid() {
+        let mut database = WadDatabase::default();
+
+        database.load_data(include_str!("resources/wad-database.ini"), "Built-in database");
+        database.validate();
+
+        assert!(database.errors().is_empty(), "{:#?}", database.errors());
+        assert!(!database.iwads.is_empty());
+    }
+
+    #[test]
+    fn user_entries_override_builtin_entries() {
+        let mut database = WadDatabase::default();
+
+        database.load_data("[Database]\nVersion=1\n[IWAD test]\nGame=DOOM\nName=A\nVersion=1\nCRC32=1\n", "A");
+        database.load_data("[Database]\nVersion=1\n[IWAD test]\nGame=DOOM2\nName=B\nVersion=2\nCRC32=0x2\n", "B");
+
+        assert!(database.errors().is_empty(), "{:#?}", database.errors());
+        assert_eq!(database.iwads.len(), 1);
+        assert_eq!(database.iwads[0].1.id, IWadID::DOOM2);
+        assert_eq!(database.iwads[0].1.crc32, Some(2));
+    }
+
+    #[test]
+    fn invalid_entries_are_reported() {
+        let mut database = WadDatabase::default();
+
+        database.load_data("[Database]\nVersion=1\n[IWAD bad]\nGame=QUAKE\nName=A\nVersion=1\nCRC32=zz\n[Other]\nName=B\n", "Test");
+
+        assert_eq!(database.errors().len(), 2, "{:#?}", database.errors());
+        assert!(database.iwads.is_empty());
+    }
+
+    #[test]
+    fn pwads_are_found_by_filename() {
+        let mut database = WadDatabase::default();
+
+        database.load_data("[Database]\nVersion=1\n[PWAD kpf]\nGame=UDOOM;DOOM2\nName=A\nDescription=B\nFile=*.kpf\nType=Resource\nRequires=KPF\n", "Test");
+
+        assert!(database.errors().is_empty(), "{:#?}", database.errors());
+
+        let data = database.find_pwad("/games/doom/ID1.KPF", 0).expect("PWAD not found");
+
+        assert_eq!(data.id, IWadID::UDOOM | IWadID::DOOM2);
+        assert_eq!(data.kind, PWadType::Resource);
+        assert_eq!(data.requires, EngineFeatures::KPF);
+        assert!(database.find_pwad("/games/doom/id1.wad", 0).is_none());
+    }
+
+    #[test]
+    fn only_marked_addons_are_expansions() {
+        let mut database = WadDatabase::default();
+
+        database.load_data("[Database]\nVersion=1\n[PWAD addon]\nGame=UDOOM\nName=A\nDescription=B\nFile=addon.wad\nWarp=5 1\n[PWAD nerve]\nGame=DOOM2\nName=C\nDescription=D\nFile=nerve.wad\nWarp=1\nExpansion=true\n[PWAD bad]\nGame=DOOM2\nName=E\nDescription=F\nFile=bad.wad\nExpansion=yes\n", "Test");
+
+        assert_eq!(database.errors().len(), 1, "{:#?}", database.errors());
+
+        assert!(!database.find_pwad("addon.wad", 0).expect("PWAD not found").is_expansion());
+        assert!(database.find_pwad("nerve.wad", 0).expect("PWAD not found").is_expansion());
+
+        // SIGIL and SIGIL II are listed as games, the compatibility version is an add-on
+        let mut database = WadDatabase::default();
+
+        database.load_data(include_str!("resources/wad-database.ini"), "Built-in database");
+
+        let expansion = |hash: u32| database.find_pwad("", hash).expect("PWAD not found").is_expansion();
+
+        assert!(expansion(0xf921_6574));
+        assert!(expansion(0xd210_db36));
+        assert!(!expansion(0xb767_9050));
+    }
+}
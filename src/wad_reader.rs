@@ -0,0 +1,775 @@
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use gtk::{gio, glib};
+use gtk::prelude::*;
+
+use crc32fast::Hasher;
+
+//------------------------------------------------------------------------------
+// CONST VARIABLES
+//------------------------------------------------------------------------------
+const WAD_HEADER_SIZE: u64 = 12;
+const WAD_LUMP_ENTRY_SIZE: u64 = 16;
+
+const ZIP_EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const ZIP_EOCD_SIZE: u64 = 22;
+const ZIP_MAX_COMMENT_SIZE: u64 = 0xffff;
+const ZIP_CENTRAL_SIGNATURE: u32 = 0x0201_4b50;
+const ZIP_CENTRAL_HEADER_SIZE: usize = 46;
+const ZIP_LOCAL_SIGNATURE: u32 = 0x0403_4b50;
+const ZIP_LOCAL_HEADER_SIZE: u64 = 30;
+
+const ZIP_METHOD_STORED: u16 = 0;
+const ZIP_METHOD_DEFLATED: u16 = 8;
+
+const ZIP_FLAG_ENCRYPTED: u16 = 0x0001;
+
+// Values marking ZIP64 fields (not supported)
+const ZIP64_U16: u16 = 0xffff;
+const ZIP64_U32: u32 = 0xffff_ffff;
+
+const SEVEN_ZIP_SIGNATURE: [u8; 6] = [b'7', b'z', 0xbc, 0xaf, 0x27, 0x1c];
+
+//------------------------------------------------------------------------------
+// GLOBAL: Helper functions
+//------------------------------------------------------------------------------
+//---------------------------------------
+// Error helper functions
+//---------------------------------------
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn unsupported(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, message)
+}
+
+//---------------------------------------
+// Read integer helper functions
+//---------------------------------------
+fn read_u16(data: &[u8], offset: usize) -> io::Result<u16> {
+    offset.checked_add(2)
+        .and_then(|end| data.get(offset..end))
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| invalid_data("Unexpected end of data"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> io::Result<u32> {
+    offset.checked_add(4)
+        .and_then(|end| data.get(offset..end))
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| invalid_data("Unexpected end of data"))
+}
+
+//------------------------------------------------------------------------------
+// ENUM: ArchiveFormat
+//------------------------------------------------------------------------------
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ArchiveFormat {
+    Wad,
+    Zip,
+    SevenZip,
+}
+
+impl ArchiveFormat {
+    //-----------------------------------
+    // Detect function
+    //-----------------------------------
+    // Detects the format from the file signature (not the extension)
+    pub fn detect(path: &Path) -> io::Result<Option<Self>> {
+        let mut signature = [0u8; 6];
+
+        let len = fs::File::open(path)?.take(signature.len() as u64).read(&mut signature)?;
+
+        let format = match &signature[..len] {
+            [b'I' | b'P', b'W', b'A', b'D', ..] => Some(Self::Wad),
+            [b'P', b'K', 3, 4, ..] | [b'P', b'K', 5, 6, ..] => Some(Self::Zip),
+            bytes if bytes == SEVEN_ZIP_SIGNATURE => Some(Self::SevenZip),
+            _ => None
+        };
+
+        Ok(format)
+    }
+}
+
+//------------------------------------------------------------------------------
+// ENUM: WadKind
+//------------------------------------------------------------------------------
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum WadKind {
+    IWad,
+    PWad,
+}
+
+//------------------------------------------------------------------------------
+// STRUCT: WadLump
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct WadLump {
+    pub name: String,
+    pub offset: u32,
+    pub size: u32,
+}
+
+//------------------------------------------------------------------------------
+// STRUCT: WadFile
+//------------------------------------------------------------------------------
+// Only the header and lump directory are read when opening; lump data is read
+// on demand
+#[derive(Debug)]
+pub struct WadFile {
+    path: PathBuf,
+    file_size: u64,
+    pub kind: WadKind,
+    pub lumps: Vec<WadLump>,
+}
+
+impl WadFile {
+    //-----------------------------------
+    // Lump name helper function
+    //-----------------------------------
+    // Names are NUL-padded, upper case ASCII (other bytes are replaced)
+    fn lump_name(bytes: &[u8]) -> String {
+        bytes.iter()
+            .take_while(|&&c| c != 0)
+            .map(|&c| if c.is_ascii_graphic() { char::from(c).to_ascii_uppercase() } else { '?' })
+            .collect()
+    }
+
+    //-----------------------------------
+    // Open function
+    //-----------------------------------
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = fs::File::open(path)?;
+
+        let file_size = file.metadata()?.len();
+
+        if file_size < WAD_HEADER_SIZE {
+            return Err(invalid_data("Not a WAD file"))
+        }
+
+        // Read header
+        let mut header = [0u8; WAD_HEADER_SIZE as usize];
+
+        file.read_exact(&mut header)?;
+
+        let kind = match &header[0..4] {
+            b"IWAD" => WadKind::IWad,
+            b"PWAD" => WadKind::PWad,
+            _ => return Err(invalid_data("Not a WAD file"))
+        };
+
+        let num_lumps = read_u32(&header, 4)?;
+        let dir_offset = read_u32(&header, 8)?;
+
+        // Check that directory lies within file (also bounds memory use)
+        let dir_size = u64::from(num_lumps) * WAD_LUMP_ENTRY_SIZE;
+
+        if u64::from(dir_offset) + dir_size > file_size {
+            return Err(invalid_data("WAD directory out of bounds"))
+        }
+
+        // Read directory
+        let mut directory = vec![0u8; dir_size as usize];
+
+        file.seek(SeekFrom::Start(u64::from(dir_offset)))?;
+        file.read_exact(&mut directory)?;
+
+        let lumps = directory.chunks_exact(WAD_LUMP_ENTRY_SIZE as usize)
+            .map(|entry| {
+                WadLump {
+                    name: Self::lump_name(&entry[8..16]),
+                    offset: u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]),
+                    size: u32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]),
+                }
+            })
+            .collect();
+
+        Ok(Self { path: path.to_path_buf(), file_size, kind, lumps })
+    }
+
+    //-----------------------------------
+    // Find lump function
+    //-----------------------------------
+    pub fn find_lump(&self, name: &str) -> Option<&WadLump> {
+        self.lumps.iter().find(|lump| lump.name.eq_ignore_ascii_case(name))
+    }
+
+    //-----------------------------------
+    // Has lump function
+    //-----------------------------------
+    pub fn has_lump(&self, name: &str) -> bool {
+        self.find_lump(name).is_some()
+    }
+
+    //-----------------------------------
+    // Read function
+    //-----------------------------------
+    // Lumps pointing outside the file are reported as invalid data
+    pub fn read(&self, lump: &WadLump) -> io::Result<Vec<u8>> {
+        if u64::from(lump.offset) + u64::from(lump.size) > self.file_size {
+            return Err(invalid_data("WAD lump out of bounds"))
+        }
+
+        let mut file = fs::File::open(&self.path)?;
+
+        let mut data = vec![0u8; lump.size as usize];
+
+        file.seek(SeekFrom::Start(u64::from(lump.offset)))?;
+        file.read_exact(&mut data)?;
+
+        Ok(data)
+    }
+
+    //-----------------------------------
+    // Read lump function
+    //-----------------------------------
+    // Returns the data of the first lump with the given name
+    pub fn read_lump(&self, name: &str) -> io::Result<Vec<u8>> {
+        let lump = self.find_lump(name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Lump not found"))?;
+
+        self.read(lump)
+    }
+}
+
+//------------------------------------------------------------------------------
+// STRUCT: ZipEntry
+//------------------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    pub name: String,
+    pub method: u16,
+    pub encrypted: bool,
+    pub crc32: u32,
+    pub compressed_size: u32,
+    pub size: u32,
+    pub header_offset: u32,
+}
+
+impl ZipEntry {
+    //-----------------------------------
+    // Is dir function
+    //-----------------------------------
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+//------------------------------------------------------------------------------
+// STRUCT: CrcReader
+//------------------------------------------------------------------------------
+// Checks the CRC-32 of the data once the expected size has been read
+struct CrcReader<R: Read> {
+    inner: R,
+    hasher: Hasher,
+    remaining: u64,
+    expected: u32,
+}
+
+impl<R: Read> Read for CrcReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+
+        self.hasher.update(&buf[..len]);
+        self.remaining = self.remaining.saturating_sub(len as u64);
+
+        if len == 0 && self.remaining > 0 {
+            return Err(invalid_data("ZIP entry truncated"))
+        }
+
+        if len > 0 && self.remaining == 0 && self.hasher.clone().finalize() != self.expected {
+            return Err(invalid_data("ZIP entry checksum mismatch"))
+        }
+
+        Ok(len)
+    }
+}
+
+//------------------------------------------------------------------------------
+// STRUCT: ZipArchive
+//------------------------------------------------------------------------------
+// Reads ZIP/PK3 archives (stored and deflated entries, no ZIP64 or encryption);
+// only the central directory is read when opening
+#[derive(Debug)]
+pub struct ZipArchive {
+    file: fs::File,
+    file_size: u64,
+    pub entries: Vec<ZipEntry>,
+}
+
+impl ZipArchive {
+    //-----------------------------------
+    // Open function
+    //-----------------------------------
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = fs::File::open(path)?;
+
+        let file_size = file.metadata()?.len();
+
+        if file_size < ZIP_EOCD_SIZE {
+            return Err(invalid_data("Not a ZIP file"))
+        }
+
+        // Find end of central directory record (followed by an optional comment)
+        let tail_size = file_size.min(ZIP_EOCD_SIZE + ZIP_MAX_COMMENT_SIZE);
+
+        let mut tail = vec![0u8; tail_size as usize];
+
+        file.seek(SeekFrom::Start(file_size - tail_size))?;
+        file.read_exact(&mut tail)?;
+
+        let eocd = (0..=tail.len() - ZIP_EOCD_SIZE as usize).rev()
+            .find(|&offset| read_u32(&tail, offset).is_ok_and(|signature| signature == ZIP_EOCD_SIGNATURE))
+            .ok_or_else(|| invalid_data("Not a ZIP file"))?;
+
+        let disk = read_u16(&tail, eocd + 4)?;
+        let num_entries = read_u16(&tail, eocd + 10)?;
+        let dir_size = read_u32(&tail, eocd + 12)?;
+        let dir_offset = read_u32(&tail, eocd + 16)?;
+
+        if disk != 0 {
+            return Err(unsupported("Multi-part ZIP archives are not supported"))
+        }
+
+        if num_entries == ZIP64_U16 || dir_size == ZIP64_U32 || dir_offset == ZIP64_U32 {
+            return Err(unsupported("ZIP64 archives are not supported"))
+        }
+
+        // Check that central directory lies within file (also bounds memory use)
+        if u64::from(dir_offset) + u64::from(dir_size) > file_size {
+            return Err(invalid_data("ZIP directory out of bounds"))
+        }
+
+        // Read central directory
+        let mut directory = vec![0u8; dir_size as usize];
+
+        file.seek(SeekFrom::Start(u64::from(dir_offset)))?;
+        file.read_exact(&mut directory)?;
+
+        // Each entry takes at least ZIP_CENTRAL_HEADER_SIZE bytes
+        let mut entries: Vec<ZipEntry> = Vec::with_capacity(usize::from(num_entries).min(directory.len() / ZIP_CENTRAL_HEADER_SIZE));
+        let mut offset = 0;
+
+        for _ in 0..num_entries {
+            if read_u32(&directory, offset)? != ZIP_CENTRAL_SIGNATURE {
+                return Err(invalid_data("Invalid ZIP directory entry"))
+            }
+
+            let name_len = usize::from(read_u16(&directory, offset + 28)?);
+            let extra_len = usize::from(read_u16(&directory, offset + 30)?);
+            let comment_len = usize::from(read_u16(&directory, offset + 32)?);
+
+            let name_start = offset + ZIP_CENTRAL_HEADER_SIZE;
+
+            let name = directory.get(name_start..name_start + name_len)
+                .map(|bytes| String::from_utf8_lossy(bytes).replace('\\', "/"))
+                .ok_or_else(|| invalid_data("Unexpected end of data"))?;
+
+            let entry = ZipEntry {
+                name,
+                method: read_u16(&directory, offset + 10)?,
+                encrypted: read_u16(&directory, offset + 8)? & ZIP_FLAG_ENCRYPTED != 0,
+                crc32: read_u32(&directory, offset + 16)?,
+                compressed_size: read_u32(&directory, offset + 20)?,
+                size: read_u32(&directory, offset + 24)?,
+                header_offset: read_u32(&directory, offset + 42)?,
+            };
+
+            if entry.compressed_size == ZIP64_U32 || entry.size == ZIP64_U32 || entry.header_offset == ZIP64_U32 {
+                return Err(unsupported("ZIP64 archives are not supported"))
+            }
+
+            entries.push(entry);
+
+            offset = name_start + name_len + extra_len + comment_len;
+        }
+
+        Ok(Self { file, file_size, entries })
+    }
+
+    //-----------------------------------
+    // Find entry function
+    //-----------------------------------
+    // Entry names are compared case-insensitively
+    pub fn find_entry(&self, name: &str) -> Option<&ZipEntry> {
+        self.entries.iter().find(|entry| entry.name.eq_ignore_ascii_case(name))
+    }
+
+    //-----------------------------------
+    // Reader function
+    //-----------------------------------
+    // Returns a reader for the (decompressed) contents of an entry; the data
+    // is checked against the entry CRC-32 once fully read
+    pub fn reader(&mut self, entry: &ZipEntry) -> io::Result<Box<dyn Read>> {
+        if entry.encrypted {
+            return Err(unsupported("Encrypted ZIP entries are not supported"))
+        }
+
+        // Skip local file header
+        let mut header = [0u8; ZIP_LOCAL_HEADER_SIZE as usize];
+
+        if u64::from(entry.header_offset) + ZIP_LOCAL_HEADER_SIZE > self.file_size {
+            return Err(invalid_data("ZIP entry out of bounds"))
+        }
+
+        self.file.seek(SeekFrom::Start(u64::from(entry.header_offset)))?;
+        self.file.read_exact(&mut header)?;
+
+        if read_u32(&header, 0)? != ZIP_LOCAL_SIGNATURE {
+            return Err(invalid_data("Invalid ZIP file header"))
+        }
+
+        let data_offset = u64::from(entry.header_offset) + ZIP_LOCAL_HEADER_SIZE +
+            u64::from(read_u16(&header, 26)?) + u64::from(read_u16(&header, 28)?);
+
+        if data_offset + u64::from(entry.compressed_size) > self.file_size {
+            return Err(invalid_data("ZIP entry out of bounds"))
+        }
+
+        self.file.seek(SeekFrom::Start(data_offset))?;
+
+        let mut data = self.file.try_clone()?.take(u64::from(entry.compressed_size));
+
+        let reader: Box<dyn Read> = match entry.method {
+            ZIP_METHOD_STORED => Box::new(data),
+            ZIP_METHOD_DEFLATED => {
+                // Inflate with GIO (raw deflate stream), reading no more than the declared size
+                let mut compressed: Vec<u8> = Vec::with_capacity(entry.compressed_size as usize);
+
+                data.read_to_end(&mut compressed)?;
+
+                let stream = gio::ConverterInputStream::new(
+                    &gio::MemoryInputStream::from_bytes(&glib::Bytes::from_owned(compressed)),
+                    &gio::ZlibDecompressor::new(gio::ZlibCompressorFormat::Raw)
+                );
+
+                Box::new(stream.into_read().take(u64::from(entry.size)))
+            },
+            _ => return Err(unsupported("Unsupported ZIP compression method"))
+        };
+
+        Ok(Box::new(CrcReader {
+            inner: reader,
+            hasher: Hasher::new(),
+            remaining: u64::from(entry.size),
+            expected: entry.crc32,
+        }))
+    }
+
+    //-----------------------------------
+    // Read function
+    //-----------------------------------
+    // Returns the contents of an entry, up to max_size bytes
+    pub fn read(&mut self, entry: &ZipEntry, max_size: u32) -> io::Result<Vec<u8>> {
+        if entry.size > max_size {
+            return Err(invalid_data("ZIP entry too large"))
+        }
+
+        let mut data: Vec<u8> = Vec::with_capacity(entry.size as usize);
+
+        self.reader(entry)?.read_to_end(&mut data)?;
+
+        Ok(data)
+    }
+
+    //-----------------------------------
+    // Extract function
+    //-----------------------------------
+    // Incomplete files are removed on error
+    pub fn extract(&mut self, entry: &ZipEntry, dest: &Path) -> io::Result<()> {
+        let mut reader = self.reader(entry)?;
+
+        let mut file = fs::File::create(dest)?;
+
+        io::copy(&mut reader, &mut file)
+            .inspect_err(|_| { let _ = fs::remove_file(dest); })?;
+
+        Ok(())
+    }
+}
+
+//------------------------------------------------------------------------------
+// ENUM: ResourceFile
+//------------------------------------------------------------------------------
+// WAD or ZIP/PK3 file, detected from its signature; PK7 (7-Zip) archives are
+// recognized but cannot be read
+#[derive(Debug)]
+pub enum ResourceFile {
+    Wad(WadFile),
+    Zip(ZipArchive),
+}
+
+impl ResourceFile {
+    //-----------------------------------
+    // Open function
+    //-----------------------------------
+    pub fn open(path: &Path) -> io::Result<Self> {
+        match ArchiveFormat::detect(path)? {
+            Some(ArchiveFormat::Wad) => WadFile::open(path).map(Self::Wad),
+            Some(ArchiveFormat::Zip) => ZipArchive::open(path).map(Self::Zip),
+            Some(ArchiveFormat::SevenZip) => Err(unsupported("7-Zip archives are not supported")),
+            None => Err(invalid_data("Unknown file format"))
+        }
+    }
+
+    //-----------------------------------
+    // Entry names function
+    //-----------------------------------
+    // Lump names for WAD files, file paths for archives (excluding folders)
+    pub fn entry_names(&self) -> Vec<&str> {
+        match self {
+            Self::Wad(wad) => wad.lumps.iter().map(|lump| lump.name.as_str()).collect(),
+            Self::Zip(zip) => {
+                zip.entries.iter()
+                    .filter(|entry| !entry.is_dir())
+                    .map(|entry| entry.name.as_str())
+                    .collect()
+            }
+        }
+    }
+
+    //-----------------------------------
+    // Read function
+    //-----------------------------------
+    // Returns the contents of a lump or archive file, up to max_size bytes
+    pub fn read(&mut self, name: &str, max_size: u32) -> io::Result<Vec<u8>> {
+        let not_found = || io::Error::new(io::ErrorKind::NotFound, "Entry not found");
+
+        match self {
+            Self::Wad(wad) => {
+                let lump = wad.find_lump(name).ok_or_else(not_found)?;
+
+                if lump.size > max_size {
+                    return Err(invalid_data("WAD lump too large"))
+                }
+
+                wad.read(lump)
+            },
+            Self::Zip(zip) => {
+                let entry = zip.find_entry(name).cloned().ok_or_else(not_found)?;
+
+                zip.read(&entry, max_size)
+            }
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// TESTS
+//------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    //---------------------------------------
+    // Test file helper functions
+    //---------------------------------------
+    fn temp_file(name: &str, data: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("dhxs-launcher-test-{}-{name}", std::process::id()));
+
+        fs::File::create(&path).and_then(|mut file| file.write_all(data)).unwrap();
+
+        path
+    }
+
+    // Builds a WAD with the given lumps (directory after lump data)
+    fn build_wad(magic: &[u8; 4], lumps: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut data: Vec<u8> = vec![];
+        let mut directory: Vec<u8> = vec![];
+
+        let mut offset = WAD_HEADER_SIZE as u32;
+
+        for (name, lump) in lumps {
+            let mut lump_name = [0u8; 8];
+            lump_name[..name.len()].copy_from_slice(name.as_bytes());
+
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(&(lump.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&lump_name);
+
+            data.extend_from_slice(lump);
+
+            offset += lump.len() as u32;
+        }
+
+        let mut wad: Vec<u8> = magic.to_vec();
+        wad.extend_from_slice(&(lumps.len() as u32).to_le_bytes());
+        wad.extend_from_slice(&offset.to_le_bytes());
+        wad.extend(data);
+        wad.extend(directory);
+
+        wad
+    }
+
+    // Builds a ZIP with stored (uncompressed) entries
+    fn build_zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip: Vec<u8> = vec![];
+        let mut directory: Vec<u8> = vec![];
+
+        for (name, data) in entries {
+            let offset = zip.len() as u32;
+            let crc = crc32fast::hash(data);
+
+            let mut header: Vec<u8> = vec![];
+            header.extend_from_slice(&[0, 0, 0, 0]);                         // version, flags
+            header.extend_from_slice(&ZIP_METHOD_STORED.to_le_bytes());
+            header.extend_from_slice(&[0, 0, 0, 0]);                         // time, date
+            header.extend_from_slice(&crc.to_le_bytes());
+            header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            header.extend_from_slice(&[0, 0]);                               // extra length
+
+            zip.extend_from_slice(&ZIP_LOCAL_SIGNATURE.to_le_bytes());
+            zip.extend_from_slice(&header);
+            zip.extend_from_slice(name.as_bytes());
+            zip.extend_from_slice(data);
+
+            directory.extend_from_slice(&ZIP_CENTRAL_SIGNATURE.to_le_bytes());
+            directory.extend_from_slice(&[0, 0]);                            // version made by
+            directory.extend_from_slice(&header);
+            directory.extend_from_slice(&[0; 10]);                           // comment, disk, attributes
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+
+        let dir_offset = zip.len() as u32;
+
+        zip.extend_from_slice(&directory);
+        zip.extend_from_slice(&ZIP_EOCD_SIGNATURE.to_le_bytes());
+        zip.extend_from_slice(&[0; 4]);                                      // disk numbers
+        zip.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&dir_offset.to_le_bytes());
+        zip.extend_from_slice(&[0, 0]);                                      // comment length
+
+        zip
+    }
+
+    //---------------------------------------
+    // WAD tests
+    //---------------------------------------
+    #[test]
+    fn wad_lumps_are_read_lazily() {
+        let path = temp_file("lumps.wad", &build_wad(b"PWAD", &[("MAP01", b""), ("things", b"1234"), ("DEHACKED", b"Patch File")]));
+
+        let wad = WadFile::open(&path).unwrap();
+
+        assert_eq!(wad.kind, WadKind::PWad);
+        assert_eq!(wad.lumps.iter().map(|lump| lump.name.as_str()).collect::<Vec<_>>(), ["MAP01", "THINGS", "DEHACKED"]);
+        assert!(wad.has_lump("dehacked"));
+        assert_eq!(wad.read_lump("THINGS").unwrap(), b"1234");
+        assert_eq!(wad.read_lump("MAP01").unwrap(), b"");
+        assert_eq!(wad.read_lump("TEXTMAP").unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn truncated_wads_are_rejected() {
+        let wad = build_wad(b"IWAD", &[("E1M1", b"data"), ("THINGS", b"1234")]);
+
+        // Directory cut off
+        let path = temp_file("truncated.wad", &wad[..wad.len() - 8]);
+        assert_eq!(WadFile::open(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Header cut off
+        let path_header = temp_file("header.wad", &wad[..6]);
+        assert_eq!(WadFile::open(&path_header).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Not a WAD
+        let path_text = temp_file("text.wad", b"Hello, World!");
+        assert_eq!(WadFile::open(&path_text).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        for path in [path, path_header, path_text] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn malicious_wads_are_rejected() {
+        // Huge lump count (must not allocate)
+        let mut wad = build_wad(b"PWAD", &[]);
+        wad[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let path = temp_file("count.wad", &wad);
+        assert_eq!(WadFile::open(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Lump pointing past end of file (directory valid)
+        let mut wad = build_wad(b"PWAD", &[("PLAYPAL", b"abcd")]);
+        let dir = wad.len() - WAD_LUMP_ENTRY_SIZE as usize;
+        wad[dir + 4..dir + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let path_lump = temp_file("lump.wad", &wad);
+        let file = WadFile::open(&path_lump).unwrap();
+        assert_eq!(file.read_lump("PLAYPAL").unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Non-ASCII lump names
+        let wad = build_wad(b"PWAD", &[("A\u{7f}B", b"")]);
+
+        let path_name = temp_file("name.wad", &wad);
+        assert_eq!(WadFile::open(&path_name).unwrap().lumps[0].name, "A?B");
+
+        for path in [path, path_lump, path_name] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    //---------------------------------------
+    // ZIP tests
+    //---------------------------------------
+    #[test]
+    fn zip_entries_are_enumerated_and_read() {
+        let path = temp_file("entries.pk3", &build_zip(&[("maps/", b""), ("maps/map01.wad", b"PWAD"), ("zscript.txt", b"version \"4.0\"")]));
+
+        assert_eq!(ArchiveFormat::detect(&path).unwrap(), Some(ArchiveFormat::Zip));
+
+        let mut file = ResourceFile::open(&path).unwrap();
+
+        assert_eq!(file.entry_names(), ["maps/map01.wad", "zscript.txt"]);
+        assert_eq!(file.read("ZSCRIPT.TXT", 1024).unwrap(), b"version \"4.0\"");
+        assert_eq!(file.read("zscript.txt", 4).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn corrupted_zips_are_rejected() {
+        let zip = build_zip(&[("decorate.txt", b"actor Foo")]);
+
+        // Central directory cut off
+        let path = temp_file("truncated.pk3", &zip[..zip.len() - 30]);
+        assert!(ZipArchive::open(&path).is_err());
+
+        // Entry data modified (checksum mismatch)
+        let mut data = zip.clone();
+        let pos = data.windows(3).position(|bytes| bytes == b"Foo").unwrap();
+        data[pos] = b'B';
+
+        let path_crc = temp_file("crc.pk3", &data);
+        let mut archive = ZipArchive::open(&path_crc).unwrap();
+        let entry = archive.entries[0].clone();
+        assert_eq!(archive.read(&entry, 1024).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Entry pointing past end of file
+        let mut archive = ZipArchive::open(&path_crc).unwrap();
+        let mut entry = archive.entries[0].clone();
+        entry.header_offset = u32::MAX - 1;
+        assert_eq!(archive.read(&entry, 1024).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // 7-Zip archives are detected but not supported
+        let path_7z = temp_file("archive.pk7", &SEVEN_ZIP_SIGNATURE);
+        assert_eq!(ArchiveFormat::detect(&path_7z).unwrap(), Some(ArchiveFormat::SevenZip));
+        assert_eq!(ResourceFile::open(&path_7z).unwrap_err().kind(), io::ErrorKind::Unsupported);
+
+        for path in [path, path_crc, path_7z] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -1,8 +1,8 @@
-use std::cell::OnceCell;
-use std::path::Path;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::collections::HashMap;
-use std::fmt::Write as _;
 
 use gtk::{gio, glib, gdk, pango};
 use adw::subclass::prelude::*;
@@ -11,15 +11,28 @@ use glib::{clone, closure_local};
 
 use crate::APP_ID;
 use crate::LauncherApp;
-use crate::engine_data::EngineSource;
+use crate::engine_data::{EngineFeatures, EngineSource, FileKind};
 use crate::engine_combo_row::EngineComboRow;
 use crate::engine_object::EngineObject;
 use crate::iwad_combo_row::IWadComboRow;
 use crate::pwad_select_row::PWadSelectRow;
+use crate::pwad_presets_dialog::PWadPresetsDialog;
 use crate::cheats_window::CheatsWindow;
+use crate::library_window::LibraryWindow;
+use crate::pwad_library_window::PWadLibraryWindow;
+use crate::import_dialog::ImportDialog;
 use crate::preferences_dialog::PreferencesDialog;
-use crate::utils::env_expand;
+use crate::utils::{env_expand, file_to_path};
+use crate::iwad_paths::iwad_search_paths;
+use crate::wad_database::WadDatabase;
 use crate::iwad_data::IWadID;
+use crate::companion_data::{CompanionLoad, COMPANION_ARRAY};
+use crate::pwad_data::{PWadData, PWadType};
+use crate::pwad_info::PWadInfo;
+use crate::pwad_preset::{PWadPreset, PWadPresetSetting};
+use crate::pwad_cache::extract_pwads;
+use crate::pwad_bundle::PWadBundle;
+use crate::wad_reader::WadFile;
 use crate::graphics_data::{GRAPHICS_PATH, GRAPHICS_MAP};
 
 //------------------------------------------------------------------------------
@@ -30,6 +43,12 @@ enum LaunchResult {
     Error(String)
 }
 
+//------------------------------------------------------------------------------
+// TYPE: GameSelection
+//------------------------------------------------------------------------------
+// PWAD files, extra switches and engine name remembered for a game
+type GameSelection = (Vec<String>, String, String);
+
 //------------------------------------------------------------------------------
 // MODULE: AppWindow
 //------------------------------------------------------------------------------
@@ -51,8 +70,12 @@ mod imp {
         #[template_child]
         pub(super) iwad_row: TemplateChild<IWadComboRow>,
         #[template_child]
+        pub(super) addons_row: TemplateChild<adw::ExpanderRow>,
+        #[template_child]
         pub(super) pwad_row: TemplateChild<PWadSelectRow>,
         #[template_child]
+        pub(super) presets_button: TemplateChild<gtk::MenuButton>,
+        #[template_child]
         pub(super) switches_row: TemplateChild<adw::EntryRow>,
 
         #[template_child]
@@ -60,6 +83,8 @@ mod imp {
 
         #[template_child]
         pub(super) launch_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) game_banner: TemplateChild<adw::Banner>,
 
         #[template_child]
         pub(super) settings_title: TemplateChild<adw::WindowTitle>,
@@ -75,7 +100,20 @@ mod imp {
         pub(super) settings_config_row: TemplateChild<adw::ActionRow>,
 
         pub(super) cheats_window: OnceCell<CheatsWindow>,
+        pub(super) library_window: OnceCell<LibraryWindow>,
+        pub(super) pwad_library_window: OnceCell<PWadLibraryWindow>,
         pub(super) prefs_dialog: OnceCell<PreferencesDialog>,
+        pub(super) import_dialog: OnceCell<ImportDialog>,
+        pub(super) presets_dialog: OnceCell<PWadPresetsDialog>,
+
+        pub(super) addon_rows: RefCell<Vec<(adw::SwitchRow, &'static PWadData, String)>>,
+        pub(super) selected_addons: RefCell<HashMap<String, Vec<String>>>,
+        pub(super) last_played: RefCell<HashMap<String, i64>>,
+        pub(super) game_selections: RefCell<HashMap<String, GameSelection>>,
+        pub(super) game_key: RefCell<Option<String>>,
+        pub(super) pwad_target: Cell<IWadID>,
+        pub(super) pwad_requires: Cell<EngineFeatures>,
+        pub(super) pwad_bundles: RefCell<Vec<String>>,
     }
 
     //-----------------------------------
@@ -116,6 +154,11 @@ mod imp {
                                 imp.engine_row.set_selected(0);
                                 imp.engine_row.reset_engine_settings();
                                 imp.iwad_row.set_selected(0);
+
+                                for (row, _, _) in imp.addon_rows.borrow().iter() {
+                                    row.set_active(false);
+                                }
+
                                 imp.pwad_row.reset_to_default();
                                 imp.switches_row.set_text("");
                             }
@@ -129,6 +172,65 @@ mod imp {
                 window.imp().cheats_window.get().unwrap().present();
             });
 
+            // Add show library window action
+            klass.install_action("win.show-library", None, |window, _, _| {
+                window.imp().library_window.get().unwrap().present();
+            });
+
+            // Add show PWAD library window action
+            klass.install_action("win.show-pwad-library", None, |window, _, _| {
+                let imp = window.imp();
+
+                let pwad_library_window = imp.pwad_library_window.get().unwrap();
+
+                pwad_library_window.set_last_played(&imp.last_played.borrow());
+
+                pwad_library_window.present();
+            });
+
+            // Add show PWAD presets dialog action
+            klass.install_action("win.show-pwad-presets", None, |window, _, _| {
+                window.imp().presets_dialog.get().unwrap().present(Some(window));
+            });
+
+            // Add save PWAD preset action (current PWAD files)
+            klass.install_action("win.save-pwad-preset", None, |window, _, _| {
+                window.show_save_preset_dialog();
+            });
+
+            // Add apply PWAD preset action (replaces PWAD files)
+            klass.install_action("win.apply-pwad-preset", Some(glib::VariantTy::UINT32), |window, _, parameter| {
+                let imp = window.imp();
+
+                if let Some(preset) = window.pwad_preset(parameter) {
+                    imp.pwad_row.set_files(preset.files);
+
+                    imp.presets_dialog.get().unwrap().close();
+                }
+            });
+
+            // Add append PWAD preset action (adds to PWAD files)
+            klass.install_action("win.append-pwad-preset", Some(glib::VariantTy::UINT32), |window, _, parameter| {
+                let imp = window.imp();
+
+                if let Some(preset) = window.pwad_preset(parameter) {
+                    imp.pwad_row.append_files(preset.files);
+
+                    imp.presets_dialog.get().unwrap().close();
+                }
+            });
+
+            // Add import game files action
+            klass.install_action("win.import-files", None, |window, _, _| {
+                let imp = window.imp();
+
+                let import_dialog = imp.import_dialog.get().unwrap();
+
+                import_dialog.set_target_folder(env_expand(&imp.prefs_dialog.get().unwrap().iwad_folder()));
+
+                import_dialog.present(Some(window));
+            });
+
             // Add show preferences action
             klass.install_action("win.show-preferences", None, |window, _, _| {
                 window.imp().prefs_dialog.get().unwrap().present(Some(window));
@@ -138,24 +240,30 @@ mod imp {
             klass.install_action("win.launch-doom", None, |window, _, _| {
                 window.set_sensitive(false);
 
-                match window.launch_doom() {
-                    LaunchResult::Error(error_msg) => {
-                        window.set_sensitive(true);
-
-                        let error_dialog = adw::AlertDialog::builder()
-                            .heading("Error")
-                            .body(error_msg)
-                            .body_use_markup(true)
-                            .build();
-
-                        error_dialog.add_responses(&[("ok", "_Ok")]);
-
-                        error_dialog.present(Some(window));
-                    },
-                    LaunchResult::Success => {
-                        window.close();
+                // Launch asynchronously (zipped PWADs may need extracting)
+                glib::spawn_future_local(clone!(
+                    #[weak] window,
+                    async move {
+                        match window.launch_doom().await {
+                            LaunchResult::Error(error_msg) => {
+                                window.set_sensitive(true);
+
+                                let error_dialog = adw::AlertDialog::builder()
+                                    .heading("Error")
+                                    .body(error_msg)
+                                    .body_use_markup(true)
+                                    .build();
+
+                                error_dialog.add_responses(&[("ok", "_Ok")]);
+
+                                error_dialog.present(Some(&window));
+                            },
+                            LaunchResult::Success => {
+                                window.close();
+                            }
+                        }
                     }
-                }
+                ));
             });
 
             //---------------------------------------
@@ -167,6 +275,12 @@ mod imp {
             // Add show cheats window shortcut
             klass.add_binding_action(gdk::Key::F1, gdk::ModifierType::empty(), "win.show-cheats");
 
+            // Add show library window shortcut
+            klass.add_binding_action(gdk::Key::L, gdk::ModifierType::CONTROL_MASK, "win.show-library");
+
+            // Add show PWAD library window shortcut
+            klass.add_binding_action(gdk::Key::L, gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::SHIFT_MASK, "win.show-pwad-library");
+
             // Add show preferences shortcut
             klass.add_binding_action(gdk::Key::comma, gdk::ModifierType::CONTROL_MASK, "win.show-preferences");
 
@@ -278,9 +392,40 @@ impl AppWindow {
 
         imp.cheats_window.set(cheats_window).unwrap();
 
+        // Create library window
+        let library_window = LibraryWindow::default();
+        library_window.set_transient_for(Some(self));
+
+        imp.library_window.set(library_window).unwrap();
+
+        // Create PWAD library window
+        let pwad_library_window = PWadLibraryWindow::default();
+        pwad_library_window.set_transient_for(Some(self));
+
+        pwad_library_window.bind_property("selected-files", &imp.pwad_row.get(), "files")
+            .sync_create()
+            .bidirectional()
+            .build();
+
+        imp.pwad_library_window.set(pwad_library_window).unwrap();
+
         // Create prefences dialog
         imp.prefs_dialog.set(PreferencesDialog::default()).unwrap();
 
+        // Create import dialog
+        imp.import_dialog.set(ImportDialog::default()).unwrap();
+
+        // Create PWAD presets dialog
+        imp.presets_dialog.set(PWadPresetsDialog::default()).unwrap();
+
+        // Build PWAD presets menu when shown (presets matching selected game and engine)
+        imp.presets_button.set_create_popup_func(clone!(
+            #[weak(rename_to = window)] self,
+            move |button| {
+                button.set_menu_model(Some(&window.presets_menu()));
+            }
+        ));
+
         // Populate switches popover
         [
             ("-fast", "Increase the speed and attack rate of monsters (requires the <b>-warp</b> parameter)"),
@@ -300,8 +445,47 @@ impl AppWindow {
             imp.switches_grid.attach(&Self::value_label(value), 1, i as i32, 1, 1);
         });
 
+        // Add PWAD files dropped onto window (from a file manager)
+        let drop_target = gtk::DropTarget::new(gdk::FileList::static_type(), gdk::DragAction::COPY);
+
+        drop_target.connect_drop(clone!(
+            #[weak] imp,
+            #[upgrade_or]
+            false,
+            move |_, value, _, _| {
+                let Ok(file_list) = value.get::<gdk::FileList>() else {
+                    return false
+                };
+
+                imp.pwad_row.append_files(file_list.files().iter().map(file_to_path).collect());
+
+                true
+            }
+        ));
+
+        self.add_controller(drop_target);
+
         // Set initial focus on engine combo row
         imp.engine_row.get().grab_focus();
+
+        // Report WAD database errors once window is shown
+        let database_errors = WadDatabase::get().errors();
+
+        if !database_errors.is_empty() {
+            glib::idle_add_local_once(clone!(
+                #[weak(rename_to = window)] self,
+                move || {
+                    let error_dialog = adw::AlertDialog::builder()
+                        .heading("WAD Database Errors")
+                        .body(database_errors.join("\n"))
+                        .build();
+
+                    error_dialog.add_responses(&[("ok", "_Ok")]);
+
+                    error_dialog.present(Some(&window));
+                }
+            ));
+        }
     }
 
     //-----------------------------------
@@ -313,6 +497,375 @@ impl AppWindow {
         imp.launch_button.set_sensitive(imp.engine_row.selected_item().is_some() && imp.iwad_row.selected_iwad().is_some());
     }
 
+    //-----------------------------------
+    // Update PWAD info helper function
+    //-----------------------------------
+    // Games compatible with all selected PWADs (empty if unknown) and engine
+    // features required by any of them
+    fn update_pwad_info(&self) {
+        let imp = self.imp();
+
+        // Mod bundles count as their files (games required by the bundle
+        // manifest replace detected ones)
+        let mut infos: Vec<PWadInfo> = vec![];
+        let mut bundles: Vec<(String, PWadBundle)> = vec![];
+
+        for file in imp.pwad_row.files() {
+            let path = Path::new(&file);
+
+            if !PWadBundle::is_bundle(path) {
+                infos.push(PWadInfo::cached_from_file(path));
+                continue
+            }
+
+            let Ok(bundle) = PWadBundle::open(path) else { continue };
+
+            infos.extend(bundle.files.iter()
+                .map(|file| {
+                    let info = PWadInfo::cached_from_file(file);
+
+                    if bundle.games.is_empty() { info } else { PWadInfo { target: bundle.games, ..info } }
+                })
+            );
+
+            bundles.push((file, bundle));
+        }
+
+        self.select_bundle_engine(&bundles);
+
+        let target = infos.iter()
+            .map(|info| info.target)
+            .filter(|target| !target.is_empty())
+            .reduce(|a, b| a & b)
+            .unwrap_or_default();
+
+        let requires = infos.iter()
+            .fold(EngineFeatures::empty(), |requires, info| requires | info.requires);
+
+        imp.pwad_target.set(target);
+        imp.pwad_requires.set(requires);
+
+        self.update_game_warning();
+        self.update_engine_requirements();
+    }
+
+    //-----------------------------------
+    // PWAD preset helper function
+    //-----------------------------------
+    // Preset at the index given as action parameter
+    fn pwad_preset(&self, parameter: Option<&glib::Variant>) -> Option<PWadPreset> {
+        let index = parameter.and_then(|parameter| parameter.get::<u32>())?;
+
+        self.imp().presets_dialog.get().unwrap().presets().into_iter()
+            .nth(index as usize)
+    }
+
+    //-----------------------------------
+    // PWAD presets menu helper function
+    //-----------------------------------
+    fn presets_menu(&self) -> gio::Menu {
+        let imp = self.imp();
+
+        let game = imp.iwad_row.selected_iwad().map(|iwad| iwad.id());
+        let source = imp.engine_row.selected_engine().map(|engine| engine.source());
+
+        let apply_section = gio::Menu::new();
+        let append_section = gio::Menu::new();
+
+        for (i, preset) in imp.presets_dialog.get().unwrap().presets().iter().enumerate() {
+            if !preset.matches(game, source) {
+                continue
+            }
+
+            // Escape mnemonic underscores
+            let label = preset.name.replace('_', "__");
+
+            for (section, action) in [(&apply_section, "win.apply-pwad-preset"), (&append_section, "win.append-pwad-preset")] {
+                let item = gio::MenuItem::new(Some(&label), None);
+                item.set_action_and_target_value(Some(action), Some(&(i as u32).to_variant()));
+
+                section.append_item(&item);
+            }
+        }
+
+        let menu = gio::Menu::new();
+
+        if apply_section.n_items() > 0 {
+            menu.append_section(Some("Use as PWAD Files"), &apply_section);
+            menu.append_section(Some("Add to PWAD Files"), &append_section);
+        }
+
+        let manage_section = gio::Menu::new();
+
+        manage_section.append(Some("Save Current Files as Preset…"), Some("win.save-pwad-preset"));
+        manage_section.append(Some("Manage Presets…"), Some("win.show-pwad-presets"));
+
+        menu.append_section(None, &manage_section);
+
+        menu
+    }
+
+    //-----------------------------------
+    // Show save preset dialog helper function
+    //-----------------------------------
+    fn show_save_preset_dialog(&self) {
+        let entry = gtk::Entry::builder()
+            .placeholder_text("Preset Name")
+            .activates_default(true)
+            .build();
+
+        let save_dialog = adw::AlertDialog::builder()
+            .heading("Save PWAD Preset")
+            .body("Save the selected PWAD files as a preset. A preset with the same name is replaced.")
+            .extra_child(&entry)
+            .default_response("save")
+            .build();
+
+        save_dialog.add_responses(&[("cancel", "_Cancel"), ("save", "_Save")]);
+        save_dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+        save_dialog.set_response_enabled("save", false);
+
+        entry.connect_changed(clone!(
+            #[weak] save_dialog,
+            move |entry| {
+                save_dialog.set_response_enabled("save", !entry.text().trim().is_empty());
+            }
+        ));
+
+        save_dialog.choose(
+            self,
+            None::<&gio::Cancellable>,
+            clone!(
+                #[weak(rename_to = window)] self,
+                move |response| {
+                    if response == "save" {
+                        let imp = window.imp();
+
+                        imp.presets_dialog.get().unwrap().save_preset(entry.text().trim(), imp.pwad_row.files());
+                    }
+                }
+            )
+        );
+    }
+
+    //-----------------------------------
+    // Store game selection helper function
+    //-----------------------------------
+    fn store_game_selection(&self, iwad_key: &str) {
+        let imp = self.imp();
+
+        let engine = imp.engine_row.selected_engine()
+            .map_or(String::new(), |engine| engine.name());
+
+        imp.game_selections.borrow_mut().insert(
+            iwad_key.to_owned(),
+            (imp.pwad_row.files(), imp.switches_row.text().to_string(), engine)
+        );
+    }
+
+    //-----------------------------------
+    // Restore game selection helper function
+    //-----------------------------------
+    // Games without a remembered selection start without PWAD files and
+    // switches, keeping the current engine
+    fn restore_game_selection(&self, iwad_key: &str) {
+        let imp = self.imp();
+
+        let (files, switches, engine) = imp.game_selections.borrow().get(iwad_key)
+            .cloned()
+            .unwrap_or_default();
+
+        imp.pwad_row.set_files(files);
+        imp.switches_row.set_text(&switches);
+
+        // Set after files (overrides engine recommended by mod bundles)
+        if !engine.is_empty() {
+            imp.engine_row.set_selected_engine_name(&engine);
+        }
+    }
+
+    //-----------------------------------
+    // Select bundle engine helper function
+    //-----------------------------------
+    // Selects the engine recommended by newly selected mod bundles (if it is
+    // available for the selected game)
+    fn select_bundle_engine(&self, bundles: &[(String, PWadBundle)]) {
+        let imp = self.imp();
+
+        let previous_bundles = imp.pwad_bundles.replace(bundles.iter()
+            .map(|(folder, _)| folder.clone())
+            .collect()
+        );
+
+        let engine_name = bundles.iter()
+            .filter(|(folder, _)| !previous_bundles.contains(folder))
+            .find_map(|(_, bundle)| bundle.engine.clone());
+
+        let Some(engine_name) = engine_name else { return };
+
+        let available = imp.engine_row.model()
+            .is_some_and(|model| model.iter::<EngineObject>().flatten().any(|engine| engine.name() == engine_name));
+
+        if available {
+            imp.engine_row.set_selected_engine_name(&engine_name);
+        }
+    }
+
+    //-----------------------------------
+    // Update engine requirements helper function
+    //-----------------------------------
+    fn update_engine_requirements(&self) {
+        let imp = self.imp();
+
+        let mut requires = imp.pwad_requires.get();
+
+        // Hexen format maps are native to Hexen
+        if imp.iwad_row.selected_iwad().is_some_and(|iwad| iwad.id() == IWadID::HEXEN) {
+            requires.remove(EngineFeatures::HEXEN_MAPS);
+        }
+
+        imp.engine_row.set_pwad_requirements(requires);
+    }
+
+    //-----------------------------------
+    // Update game warning helper function
+    //-----------------------------------
+    // Warns if the selected PWADs are not made for the selected game, offering
+    // to switch to a compatible installed one
+    fn update_game_warning(&self) {
+        let imp = self.imp();
+
+        let target = imp.pwad_target.get();
+
+        let mismatched_iwad = imp.iwad_row.selected_iwad()
+            .filter(|iwad| !target.is_empty() && !target.contains(iwad.id()));
+
+        if let Some(iwad) = &mismatched_iwad {
+            let compatible_iwad = imp.iwad_row.compatible_iwad(target);
+
+            imp.game_banner.set_title(&format!("The selected PWAD files are not made for {}", iwad.name()));
+            imp.game_banner.set_button_label(compatible_iwad.map(|iwad| format!("Switch to {}", iwad.name())).as_deref());
+        }
+
+        imp.game_banner.set_revealed(mismatched_iwad.is_some());
+    }
+
+    //-----------------------------------
+    // Scan IWAD folders helper function
+    //-----------------------------------
+    fn scan_iwad_folders(&self) {
+        let imp = self.imp();
+
+        let prefs_dialog = imp.prefs_dialog.get().unwrap();
+
+        let disabled_paths = prefs_dialog.disabled_iwad_paths();
+
+        let folders = iwad_search_paths(&env_expand(&prefs_dialog.iwad_folder())).into_iter()
+            .filter(|folder| !disabled_paths.contains(&folder.path))
+            .collect::<Vec<_>>();
+
+        imp.iwad_row.init_for_folders(&folders, &prefs_dialog.preferred_iwad_source());
+
+        let library_window = imp.library_window.get().unwrap();
+
+        library_window.set_iwad_folder(env_expand(&prefs_dialog.iwad_folder()));
+        library_window.set_entries(&imp.iwad_row.library());
+
+        self.set_launch_button_state();
+    }
+
+    //-----------------------------------
+    // Populate add-ons helper function
+    //-----------------------------------
+    fn populate_addons(&self) {
+        let imp = self.imp();
+
+        // Remove existing add-on rows
+        for (row, _, _) in imp.addon_rows.take() {
+            imp.addons_row.remove(&row);
+        }
+
+        let Some(iwad) = imp.iwad_row.selected_iwad() else {
+            imp.addons_row.set_visible(false);
+            return
+        };
+
+        let iwad_key = iwad.selection_key();
+
+        let selected = imp.selected_addons.borrow().get(&iwad_key)
+            .cloned()
+            .unwrap_or_default();
+
+        // Add rows for add-ons of selected IWAD
+        let addons: Vec<(adw::SwitchRow, &'static PWadData, String)> = iwad.optional_pwads()
+            .into_iter()
+            .filter(|(pwad_data, _)| pwad_data.kind == PWadType::Addon)
+            .map(|(pwad_data, filename)| {
+                let row = adw::SwitchRow::builder()
+                    .title(pwad_data.name.as_str())
+                    .title_lines(1)
+                    .active(selected.contains(&filename))
+                    .build();
+
+                // Remember add-on selection for IWAD
+                row.connect_active_notify(clone!(
+                    #[weak] imp,
+                    #[strong] iwad_key,
+                    #[strong] filename,
+                    move |row| {
+                        let mut selected_addons = imp.selected_addons.borrow_mut();
+
+                        let selected = selected_addons.entry(iwad_key.clone()).or_default();
+
+                        selected.retain(|file| *file != filename);
+
+                        if row.is_active() {
+                            selected.push(filename.clone());
+                        }
+
+                        if selected.is_empty() {
+                            selected_addons.remove(&iwad_key);
+                        }
+                    }
+                ));
+
+                imp.addons_row.add_row(&row);
+
+                (row, pwad_data, filename)
+            })
+            .collect();
+
+        imp.addons_row.set_visible(!addons.is_empty());
+
+        imp.addon_rows.replace(addons);
+
+        self.update_addons_state();
+    }
+
+    //-----------------------------------
+    // Update add-ons state helper function
+    //-----------------------------------
+    fn update_addons_state(&self) {
+        let imp = self.imp();
+
+        let features = imp.engine_row.selected_engine()
+            .map(|engine| engine.features())
+            .unwrap_or_default();
+
+        // Disable add-ons that the selected engine cannot load
+        for (row, pwad_data, _) in imp.addon_rows.borrow().iter() {
+            let supported = features.contains(pwad_data.requires);
+
+            row.set_sensitive(supported);
+
+            if supported {
+                row.set_subtitle(&pwad_data.description);
+            } else {
+                row.set_subtitle(&format!("Requires engine support for {}", pwad_data.requires.display_name()));
+            }
+        }
+    }
+
     //-----------------------------------
     // Setup signals
     //-----------------------------------
@@ -324,19 +877,65 @@ impl AppWindow {
         // Preferences window IWAD folder property notify signal
         prefs_dialog.connect_iwad_folder_notify(clone!(
             #[weak(rename_to = window)] self,
-            #[weak] imp,
-            move |prefs_dialog| {
-                imp.iwad_row.init_for_folders(&env_expand(&prefs_dialog.iwad_folder()));
+            move |_| {
+                window.scan_iwad_folders();
+            }
+        ));
 
-                window.set_launch_button_state();
+        // Preferences window disabled IWAD paths property notify signal
+        prefs_dialog.connect_disabled_iwad_paths_notify(clone!(
+            #[weak(rename_to = window)] self,
+            move |_| {
+                window.scan_iwad_folders();
+            }
+        ));
+
+        // Preferences window preferred IWAD source property notify signal
+        prefs_dialog.connect_preferred_iwad_source_notify(clone!(
+            #[weak(rename_to = window)] self,
+            move |_| {
+                window.scan_iwad_folders();
+            }
+        ));
+
+        // Preferences window search paths refreshed signal
+        prefs_dialog.connect_closure("search-paths-refreshed", false, closure_local!(
+            #[watch(rename_to = window)] self,
+            move |_: PreferencesDialog| {
+                window.scan_iwad_folders();
+            }
+        ));
+
+        // Library window files changed signal
+        imp.library_window.get().unwrap().connect_closure("files-changed", false, closure_local!(
+            #[watch(rename_to = window)] self,
+            move |_: LibraryWindow| {
+                window.scan_iwad_folders();
+            }
+        ));
+
+        // Import dialog imported signal
+        imp.import_dialog.get().unwrap().connect_closure("imported", false, closure_local!(
+            #[watch(rename_to = window)] self,
+            move |_: ImportDialog| {
+                window.scan_iwad_folders();
             }
         ));
 
+        // Preferences window hide incompatible engines property binding
+        prefs_dialog.bind_property("hide-incompatible-engines", &imp.engine_row.get(), "hide-incompatible")
+            .sync_create()
+            .build();
+
         // Preferences window PWAD folder property notify signal
         prefs_dialog.connect_pwad_folder_notify(clone!(
             #[weak] imp,
             move |prefs_dialog| {
                 imp.pwad_row.set_initial_folder(prefs_dialog.pwad_folder());
+
+                imp.pwad_library_window.get().unwrap().set_pwad_folder(env_expand(&prefs_dialog.pwad_folder()));
+
+                imp.presets_dialog.get().unwrap().set_initial_folder(prefs_dialog.pwad_folder());
             }
         ));
 
@@ -344,6 +943,8 @@ impl AppWindow {
         imp.engine_row.connect_selected_item_notify(clone!(
             #[weak(rename_to = window)] self,
             move |_| {
+                window.update_addons_state();
+
                 window.set_launch_button_state();
             }
         ));
@@ -359,18 +960,7 @@ impl AppWindow {
 
                     imp.settings_desc_row.set_subtitle(&engine.description());
 
-                    let games = glib::FlagsValue::from_value(&engine.games().to_value())
-                        .map(|(_, values)| {
-                            let mut games_list: Vec<&str> = values.iter()
-                                .map(|v| v.name())
-                                .collect();
-
-                            games_list.sort_unstable();
-                            games_list.dedup();
-
-                            games_list.join(" \u{2022} ")
-                        })
-                        .unwrap_or_default();
+                    let games = engine.games().display_names().join(" \u{2022} ");
 
                     imp.settings_games_row.set_subtitle(&games);
 
@@ -424,9 +1014,50 @@ impl AppWindow {
             #[weak(rename_to = window)] self,
             #[weak] imp,
             move |iwad_row| {
-                imp.engine_row.filter_engines(iwad_row.selected_iwad().map(|iwad| iwad.id()));
+                // Swap remembered selection (not when first populated)
+                let iwad_key = iwad_row.selected_iwad().map(|iwad| iwad.game_key());
+
+                let previous_key = imp.game_key.replace(iwad_key.clone());
+
+                let swap_selection = previous_key.is_some() && iwad_key != previous_key
+                    && imp.prefs_dialog.get().unwrap().remember_game_selections();
+
+                if let Some(key) = previous_key.as_deref().filter(|_| swap_selection) {
+                    window.store_game_selection(key);
+                }
+
+                imp.engine_row.filter_engines(iwad_row.selected_iwad().map(|iwad| (iwad.id(), iwad.requires())));
+
+                if let Some(key) = iwad_key.as_deref().filter(|_| swap_selection) {
+                    window.restore_game_selection(key);
+                }
+
+                window.populate_addons();
 
                 window.set_launch_button_state();
+
+                window.update_game_warning();
+                window.update_engine_requirements();
+            }
+        ));
+
+        // PWAD select row files property notify signal
+        imp.pwad_row.connect_files_notify(clone!(
+            #[weak(rename_to = window)] self,
+            move |pwad_row| {
+                window.update_pwad_info();
+
+                window.action_set_enabled("win.save-pwad-preset", !pwad_row.files().is_empty());
+            }
+        ));
+
+        // Game banner button clicked signal (switch to compatible game)
+        imp.game_banner.connect_button_clicked(clone!(
+            #[weak] imp,
+            move |_| {
+                if let Some(iwad) = imp.iwad_row.compatible_iwad(imp.pwad_target.get()) {
+                    imp.iwad_row.set_selected_iwad_key(&iwad.selection_key());
+                }
             }
         ));
     }
@@ -467,15 +1098,44 @@ impl AppWindow {
         prefs_dialog.set_iwad_default_folder(Self::gsetting_default_value(&gsettings,"iwad-folder"));
         prefs_dialog.set_pwad_default_folder(Self::gsetting_default_value(&gsettings,"pwad-folder"));
 
+        prefs_dialog.set_disabled_iwad_paths(gsettings.strv("disabled-iwad-paths").into_iter().map(String::from).collect::<Vec<String>>());
+        prefs_dialog.set_preferred_iwad_source(gsettings.string("preferred-iwad-source"));
         prefs_dialog.set_iwad_folder(gsettings.string("iwad-folder"));
         prefs_dialog.set_pwad_folder(gsettings.string("pwad-folder"));
+        prefs_dialog.set_remember_game_selections(gsettings.boolean("remember-game-selections"));
+        prefs_dialog.set_hide_incompatible_engines(gsettings.boolean("hide-incompatible-engines"));
 
         // Init main window
         imp.engine_row.set_selected_engine_name(&gsettings.string("selected-engine"));
-        imp.iwad_row.set_selected_iwad_file(&gsettings.string("selected-iwad"));
-        imp.pwad_row.set_files(gsettings.strv("pwad-files").into_iter().map(String::from).collect::<Vec<String>>());
+        imp.iwad_row.set_preferred_iwads(gsettings.strv("preferred-iwads").into_iter().map(String::from).collect::<Vec<String>>());
+        imp.iwad_row.set_selected_iwad_key(&gsettings.string("selected-iwad"));
+
+        // Mod bundles already selected do not change the selected engine
+        let pwad_files = gsettings.strv("pwad-files").into_iter().map(String::from).collect::<Vec<String>>();
+
+        imp.pwad_bundles.replace(pwad_files.iter()
+            .filter(|file| PWadBundle::is_bundle(Path::new(file)))
+            .cloned()
+            .collect()
+        );
+
+        imp.pwad_row.set_files(pwad_files);
+
+        imp.selected_addons.replace(gsettings.get::<HashMap<String, Vec<String>>>("selected-addons"));
+        imp.last_played.replace(gsettings.get::<HashMap<String, i64>>("pwad-last-played"));
+
+        imp.presets_dialog.get().unwrap().set_presets(gsettings.get::<Vec<PWadPresetSetting>>("pwad-presets").into_iter()
+            .map(PWadPreset::from_setting)
+            .collect()
+        );
+
+        self.populate_addons();
         imp.switches_row.set_text(&gsettings.string("extra-switches"));
 
+        // Load remembered selections last (selections stored while restoring
+        // the main window are discarded)
+        imp.game_selections.replace(gsettings.get::<HashMap<String, GameSelection>>("game-selections"));
+
         // Init engine settings
         for engine in imp.engine_row.engines().iter::<EngineObject>().flatten() {
             if engine.source() == EngineSource::ZDoom {
@@ -501,19 +1161,37 @@ impl AppWindow {
 
         // Get selected IWAD
         let selected_iwad = imp.iwad_row.selected_iwad()
-            .map_or(String::new(), |iwad| iwad.filename());
+            .map_or(String::new(), |iwad| iwad.selection_key());
 
         // Save main window settings
         Self::set_gsetting(&gsettings, "selected-engine", &selected_engine);
         Self::set_gsetting(&gsettings, "selected-iwad", &selected_iwad);
+        Self::set_gsetting(&gsettings, "preferred-iwads", &imp.iwad_row.preferred_iwads());
         Self::set_gsetting(&gsettings, "pwad-files", &imp.pwad_row.files());
+        Self::set_gsetting(&gsettings, "selected-addons", &*imp.selected_addons.borrow());
+        Self::set_gsetting(&gsettings, "pwad-last-played", &*imp.last_played.borrow());
+        Self::set_gsetting(&gsettings, "pwad-presets", &imp.presets_dialog.get().unwrap().presets().iter()
+            .map(PWadPreset::to_setting)
+            .collect::<Vec<PWadPresetSetting>>()
+        );
         Self::set_gsetting(&gsettings, "extra-switches", &imp.switches_row.text().to_string());
 
+        // Save remembered selections (including current game)
+        if let Some(key) = imp.game_key.borrow().as_deref().filter(|_| imp.prefs_dialog.get().unwrap().remember_game_selections()) {
+            self.store_game_selection(key);
+        }
+
+        Self::set_gsetting(&gsettings, "game-selections", &*imp.game_selections.borrow());
+
         // Save preferences window settings
         let prefs_dialog = imp.prefs_dialog.get().unwrap();
 
         Self::set_gsetting(&gsettings, "iwad-folder", &prefs_dialog.iwad_folder());
         Self::set_gsetting(&gsettings, "pwad-folder", &prefs_dialog.pwad_folder());
+        Self::set_gsetting(&gsettings, "disabled-iwad-paths", &prefs_dialog.disabled_iwad_paths());
+        Self::set_gsetting(&gsettings, "preferred-iwad-source", &prefs_dialog.preferred_iwad_source());
+        Self::set_gsetting(&gsettings, "remember-game-selections", &prefs_dialog.remember_game_selections());
+        Self::set_gsetting(&gsettings, "hide-incompatible-engines", &prefs_dialog.hide_incompatible_engines());
 
         // Save engine settings
         for engine in imp.engine_row.engines().iter::<EngineObject>().flatten() {
@@ -529,7 +1207,7 @@ impl AppWindow {
     // Launch Doom function
     //-----------------------------------
     #[allow(clippy::zombie_processes)]
-    fn launch_doom(&self) -> LaunchResult {
+    async fn launch_doom(&self) -> LaunchResult {
         let imp = self.imp();
 
         // Return with error if no engine selected
@@ -544,10 +1222,7 @@ impl AppWindow {
 
         // Get executable file
         let exec_file = env_expand(&match iwad.id() {
-            IWadID::DOOM | IWadID::UDOOM | IWadID::DOOM2 | IWadID::PLUTONIA | IWadID::TNT | IWadID::FREEDOOM1 | IWadID::FREEDOOM2 | IWadID::HACX => {
-                engine.doom_path()
-            },
-            IWadID::HERETIC => {
+            id if id.intersects(IWadID::ANY_HERETIC) => {
                 engine.heretic_path().unwrap_or_else(|| engine.doom_path())
             },
             IWadID::HEXEN => {
@@ -556,7 +1231,9 @@ impl AppWindow {
             IWadID::STRIFE => {
                 engine.strife_path().unwrap_or_else(|| engine.doom_path())
             },
-            _ => unreachable!()
+            _ => {
+                engine.doom_path()
+            }
         });
 
         // Return with error if executable file does not exist
@@ -572,29 +1249,70 @@ impl AppWindow {
         }
 
         // Init Doom command line with exec file and IWAD
-        let mut cmd_line = format!("{exec_file} -iwad {iwad_file}");
+        let mut args: Vec<String> = vec![exec_file, String::from("-iwad"), iwad_file.clone()];
+
+        // Return with error if a companion file required by engine is missing
+        let companions = iwad.companions();
+
+        let missing_companion = COMPANION_ARRAY.iter()
+            .filter(|data| data.id == iwad.id() && data.required)
+            .filter(|data| data.kind.load(engine.source()) != CompanionLoad::NotNeeded)
+            .find(|data| !companions.iter().any(|(companion, _)| std::ptr::eq(*companion, *data)));
 
-        // Get installed PWAD files for IWAD
-        let pwad_files = iwad.pwad_files().join(" ");
+        if let Some(data) = missing_companion {
+            let companion_file = Path::new(&iwad_file).with_file_name(data.filename);
 
-        if !pwad_files.is_empty() {
-            write!(cmd_line, " -file {pwad_files}").unwrap();
+            return LaunchResult::Error(format!("File <b>{}</b> ({}) required by {} not found.", companion_file.display(), data.name, iwad.name()))
         }
 
-        // Get user PWAD files
-        let user_pwad_files = imp.pwad_row.files().join(" ");
+        // Get files loaded by engine, in load order (expansion, add-ons, user
+        // PWADs, hires graphics)
+        let mut files: Vec<String> = vec![];
 
-        if !user_pwad_files.is_empty() {
-            write!(cmd_line, " -file {user_pwad_files}").unwrap();
+        if let Some(expansion_file) = iwad.expansion_file() {
+            files.push(expansion_file);
         }
 
-        // Get extra switches
-        let extra_switches = imp.switches_row.text();
+        // Get selected add-on files and resource files supported by engine
+        let features = engine.features();
+
+        files.extend(imp.addon_rows.borrow().iter()
+            .filter(|(row, pwad_data, _)| row.is_active() && features.contains(pwad_data.requires))
+            .map(|(_, _, filename)| filename.clone())
+        );
+
+        files.extend(iwad.optional_pwads().into_iter()
+            .filter(|(pwad_data, _)| pwad_data.kind == PWadType::Resource && features.contains(pwad_data.requires))
+            .map(|(_, filename)| filename)
+        );
+
+        // Get user PWAD files (expanding mod bundles)
+        let mut bundle_switches: Vec<String> = vec![];
+
+        for file in imp.pwad_row.files() {
+            if !PWadBundle::is_bundle(Path::new(&file)) {
+                files.push(file);
+                continue
+            }
 
-        if !extra_switches.is_empty() {
-            write!(cmd_line, " {extra_switches}").unwrap();
+            match PWadBundle::open(Path::new(&file)) {
+                Ok(bundle) => {
+                    files.extend(bundle.files.iter().map(|path| path.display().to_string()));
+                    bundle_switches.extend(bundle.switches);
+                },
+                Err(error) => {
+                    return LaunchResult::Error(format!("Error reading mod bundle <b>{file}</b>: {error}."))
+                }
+            }
         }
 
+        // Additional switches come first (engines use the first occurrence of
+        // a switch, so they override bundle switches)
+        let switches = [imp.switches_row.text().to_string()].into_iter()
+            .chain(bundle_switches)
+            .collect::<Vec<String>>()
+            .join(" ");
+
         // Get hires graphics files if enabled
         let load_graphics = (engine.source() == EngineSource::ZDoom) && engine.settings().hires() &&
             Path::new(GRAPHICS_PATH).try_exists().unwrap_or_default();
@@ -602,28 +1320,111 @@ impl AppWindow {
         if load_graphics {
             let graphics_map = HashMap::from(GRAPHICS_MAP);
 
-            let graphics_files = graphics_map.get(&iwad.id())
-                .map(|files| {
-                    files.iter()
-                        .map(|file| Path::new(GRAPHICS_PATH).join(file).display().to_string())
-                        .collect::<Vec<String>>()
-                        .join(" ")
-                })
-                .unwrap_or_default();
+            if let Some(graphics_files) = graphics_map.get(&iwad.id()) {
+                files.extend(graphics_files.iter().map(|file| Path::new(GRAPHICS_PATH).join(file).display().to_string()));
+            }
+        }
+
+        // Extract zipped PWADs for engines that cannot load ZIP archives (PK3
+        // archives are never extracted), in a worker thread
+        let mut loaded_files: Vec<(String, FileKind)> = vec![];
+
+        for file in files {
+            let kind = FileKind::from_path(Path::new(&file));
+
+            if kind == FileKind::Zip && kind.load(engine.source()).is_none() {
+                let archive = PathBuf::from(&file);
+
+                let result = gio::spawn_blocking(move || extract_pwads(&archive))
+                    .await
+                    .unwrap_or_else(|_| Err(std::io::Error::other("extraction failed")));
 
-            if !graphics_files.is_empty() {
-                write!(cmd_line, " -file {graphics_files}").unwrap();
+                match result {
+                    Ok(extracted_files) => {
+                        loaded_files.extend(extracted_files.iter().map(|path| (path.display().to_string(), FileKind::from_path(path))));
+                    },
+                    Err(error) => {
+                        return LaunchResult::Error(format!("Error extracting <b>{file}</b>: {error}."))
+                    }
+                }
+            } else {
+                loaded_files.push((file, kind));
             }
         }
 
-        // Launch Doom
-        let Some(params) = shlex::split(&cmd_line).filter(|params| !params.is_empty()) else {
-            return LaunchResult::Error(String::from("Error parsing command line."))
+        // Load embedded DeHackEd patches if not applied automatically by engine
+        let load_dehacked_lumps = !features.contains(EngineFeatures::DEHACKED_LUMPS) &&
+            !switches.contains("-dehlump") &&
+            loaded_files.iter()
+                .filter(|(_, kind)| matches!(kind, FileKind::Wad | FileKind::SpriteWad))
+                .any(|(file, _)| WadFile::open(Path::new(file)).is_ok_and(|wad| wad.has_lump("DEHACKED")));
+
+        if load_dehacked_lumps {
+            args.push(String::from("-dehlump"));
+        }
+
+        // Route files to the switches used by engine, companion files first
+        // (return with error if a file type is not supported)
+        let mut routed_files: Vec<(String, &str)> = companions.iter()
+            .filter_map(|(data, companion_file)| match data.kind.load(engine.source()) {
+                CompanionLoad::Switch(switch) => Some((companion_file.clone(), switch)),
+                _ => None
+            })
+            .collect();
+
+        for (file, kind) in loaded_files {
+            let Some(switch) = kind.load(engine.source()) else {
+                return LaunchResult::Error(format!("{} cannot load {} (<b>{file}</b>).", engine.name(), kind.display_name()))
+            };
+
+            routed_files.push((file, switch));
+        }
+
+        let mut file_groups: Vec<(&str, Vec<String>)> = vec![];
+
+        for (file, switch) in routed_files {
+            let group = if engine.source().groups_files() {
+                file_groups.iter_mut().find(|(group_switch, _)| *group_switch == switch)
+            } else {
+                file_groups.last_mut().filter(|(group_switch, _)| *group_switch == switch)
+            };
+
+            match group {
+                Some((_, group_files)) => group_files.push(file),
+                None => file_groups.push((switch, vec![file]))
+            }
+        }
+
+        for (switch, group_files) in file_groups {
+            args.push(String::from(switch));
+            args.extend(group_files);
+        }
+
+        // Get default warp for expansion
+        if let Some(warp) = iwad.expansion_file().and(iwad.warp()).filter(|_| !switches.contains("-warp")) {
+            args.push(String::from("-warp"));
+            args.extend(warp.split_whitespace().map(String::from));
+        }
+
+        // Get extra switches
+        let Some(extra_switches) = shlex::split(&switches) else {
+            return LaunchResult::Error(String::from("Error parsing additional switches."))
         };
 
-        Command::new(&params[0])
-            .args(&params[1..])
-            .spawn()
-            .map_or(LaunchResult::Error(String::from("Error spawning command.")), |_| LaunchResult::Success)
+        args.extend(extra_switches);
+
+        // Launch Doom
+        if Command::new(&args[0]).args(&args[1..]).spawn().is_err() {
+            return LaunchResult::Error(String::from("Error spawning command."))
+        }
+
+        // Remember launch time of user PWADs (shown in PWAD library)
+        let now = glib::DateTime::now_local()
+            .map(|date| date.to_unix())
+            .unwrap_or_default();
+
+        imp.last_played.borrow_mut().extend(imp.pwad_row.files().into_iter().map(|file| (file, now)));
+
+        LaunchResult::Success
     }
 }